@@ -0,0 +1,43 @@
+//! Let the bot play a few runs headless and print how each difficulty went
+//!
+//! `cargo run --example bot_playtest -- <runs>`
+
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*};
+use jam7::{
+    bot::{BotPlugin, BotReport},
+    game::AppState,
+    headless::headless_app,
+    player::PlayerPlugin,
+};
+
+fn main() {
+    let runs = std::env::args()
+        .nth(1)
+        .and_then(|runs| runs.parse::<usize>().ok())
+        .unwrap_or(3);
+
+    let mut app = headless_app(Duration::from_millis(50));
+    app.add_plugins((InputPlugin, PlayerPlugin, BotPlugin));
+    app.update();
+
+    for run in 1..=runs {
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::Loading);
+
+        while *app.world().resource::<State<AppState>>() != AppState::ScoreMenu {
+            app.update();
+        }
+
+        println!("Run {run} done");
+
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::MainMenu);
+        app.update();
+    }
+
+    println!("{}", app.world().resource::<BotReport>().summary());
+}
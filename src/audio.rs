@@ -9,6 +9,8 @@ use crate::{
     menus::ButtonClicked,
     physics::{PlayerHitPowerup, PlayerHitTarget},
     player::PlayerJump,
+    powerup::PowerupKind,
+    seed::AmbientRng,
 };
 
 pub struct AudioPlugin;
@@ -35,7 +37,7 @@ fn queue_music(
     mut commands: Commands,
     mut level_handles: ResMut<LevelAssetHandles>,
     music_player: Query<&MusicPlayer>,
-    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<bevy_rand::global::GlobalRng>>,
+    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<AmbientRng>>,
     settings: Res<GameSettings>,
 ) {
     if music_player.is_empty() {
@@ -83,15 +85,27 @@ fn on_jump(
 }
 
 fn on_powerup(
-    _: On<PlayerHitPowerup>,
+    trigger: On<PlayerHitPowerup>,
     mut commands: Commands,
+    powerups: Query<&PowerupKind>,
     handles: Res<PermanentAssetHandles>,
     settings: Res<GameSettings>,
 ) {
-    commands.spawn(
-        SamplePlayer::new(handles.powerup_sound.clone())
-            .with_volume(Volume::Linear(settings.sfx_volume * 0.01)),
-    );
+    let Some((kind, sound)) = powerups
+        .get(trigger.0)
+        .ok()
+        .and_then(|kind| Some((kind, handles.powerup_sounds.get(kind)?)))
+    else {
+        return;
+    };
+
+    commands.spawn((
+        SamplePlayer::new(sound.clone()).with_volume(Volume::Linear(settings.sfx_volume * 0.01)),
+        PlaybackSettings {
+            speed: kind.pitch(),
+            ..default()
+        },
+    ));
 }
 
 fn on_target(
@@ -0,0 +1,206 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Keys and buttons of the player actions, saved with the settings
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputBindings {
+    pub forward: KeyCode,
+    pub backward: KeyCode,
+    pub left: KeyCode,
+    pub right: KeyCode,
+    pub jump: KeyCode,
+    pub menu: KeyCode,
+    pub gamepad_jump: GamepadButton,
+    pub gamepad_menu: GamepadButton,
+    /// Second gamepad button opening the menu
+    pub gamepad_menu_alt: GamepadButton,
+    /// Move with the right stick and look around with the left one
+    pub swap_sticks: bool,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        Self {
+            forward: KeyCode::KeyW,
+            backward: KeyCode::KeyS,
+            left: KeyCode::KeyA,
+            right: KeyCode::KeyD,
+            jump: KeyCode::Space,
+            menu: KeyCode::Escape,
+            gamepad_jump: GamepadButton::South,
+            gamepad_menu: GamepadButton::Start,
+            gamepad_menu_alt: GamepadButton::Select,
+            swap_sticks: false,
+        }
+    }
+}
+
+/// One rebindable input of [`InputBindings`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BindingSlot {
+    Forward,
+    Backward,
+    Left,
+    Right,
+    Jump,
+    Menu,
+    GamepadJump,
+    GamepadMenu,
+    GamepadMenuAlt,
+}
+
+impl BindingSlot {
+    pub const ALL: [Self; 9] = [
+        Self::Forward,
+        Self::Backward,
+        Self::Left,
+        Self::Right,
+        Self::Jump,
+        Self::Menu,
+        Self::GamepadJump,
+        Self::GamepadMenu,
+        Self::GamepadMenuAlt,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Forward => "Forward",
+            Self::Backward => "Backward",
+            Self::Left => "Left",
+            Self::Right => "Right",
+            Self::Jump => "Jump",
+            Self::Menu => "Menu",
+            Self::GamepadJump => "Jump (gamepad)",
+            Self::GamepadMenu => "Menu (gamepad)",
+            Self::GamepadMenuAlt => "Menu (gamepad, alt)",
+        }
+    }
+
+    pub fn is_gamepad(self) -> bool {
+        matches!(
+            self,
+            Self::GamepadJump | Self::GamepadMenu | Self::GamepadMenuAlt
+        )
+    }
+}
+
+/// Outcome of the inputs pressed while a slot waits for a new key or button
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Capture {
+    /// Nothing was pressed yet
+    Waiting,
+    /// A key was pressed for a gamepad slot, or a button for a keyboard one
+    Cancelled,
+    /// The slot got its new binding, with the slot which got the previous one after a conflict
+    Bound(Option<BindingSlot>),
+}
+
+impl InputBindings {
+    pub fn key(&self, slot: BindingSlot) -> Option<KeyCode> {
+        match slot {
+            BindingSlot::Forward => Some(self.forward),
+            BindingSlot::Backward => Some(self.backward),
+            BindingSlot::Left => Some(self.left),
+            BindingSlot::Right => Some(self.right),
+            BindingSlot::Jump => Some(self.jump),
+            BindingSlot::Menu => Some(self.menu),
+            BindingSlot::GamepadJump | BindingSlot::GamepadMenu | BindingSlot::GamepadMenuAlt => {
+                None
+            }
+        }
+    }
+
+    pub fn button(&self, slot: BindingSlot) -> Option<GamepadButton> {
+        match slot {
+            BindingSlot::GamepadJump => Some(self.gamepad_jump),
+            BindingSlot::GamepadMenu => Some(self.gamepad_menu),
+            BindingSlot::GamepadMenuAlt => Some(self.gamepad_menu_alt),
+            _ => None,
+        }
+    }
+
+    fn key_mut(&mut self, slot: BindingSlot) -> Option<&mut KeyCode> {
+        match slot {
+            BindingSlot::Forward => Some(&mut self.forward),
+            BindingSlot::Backward => Some(&mut self.backward),
+            BindingSlot::Left => Some(&mut self.left),
+            BindingSlot::Right => Some(&mut self.right),
+            BindingSlot::Jump => Some(&mut self.jump),
+            BindingSlot::Menu => Some(&mut self.menu),
+            BindingSlot::GamepadJump | BindingSlot::GamepadMenu | BindingSlot::GamepadMenuAlt => {
+                None
+            }
+        }
+    }
+
+    fn button_mut(&mut self, slot: BindingSlot) -> Option<&mut GamepadButton> {
+        match slot {
+            BindingSlot::GamepadJump => Some(&mut self.gamepad_jump),
+            BindingSlot::GamepadMenu => Some(&mut self.gamepad_menu),
+            BindingSlot::GamepadMenuAlt => Some(&mut self.gamepad_menu_alt),
+            _ => None,
+        }
+    }
+
+    /// Name of the key or button bound to `slot`
+    pub fn name(&self, slot: BindingSlot) -> String {
+        match (self.key(slot), self.button(slot)) {
+            (Some(key), _) => format!("{key:?}")
+                .trim_start_matches("Key")
+                .trim_start_matches("Digit")
+                .to_string(),
+            (None, Some(button)) => format!("{button:?}"),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Bind `key` to `slot`, returns the slot which was already using it
+    ///
+    /// That slot gets the previous key of `slot`, so no action is left without a key
+    pub fn set_key(&mut self, slot: BindingSlot, key: KeyCode) -> Option<BindingSlot> {
+        let previous = self.key(slot)?;
+        let conflict = BindingSlot::ALL
+            .into_iter()
+            .find(|other| *other != slot && self.key(*other) == Some(key));
+
+        if let Some(other) = conflict {
+            *self.key_mut(other)? = previous;
+        }
+        *self.key_mut(slot)? = key;
+
+        conflict
+    }
+
+    /// Bind the `key` or `button` just pressed to `slot`, pressing the other kind of input cancels
+    ///
+    /// Every key can be bound, Escape included
+    pub fn capture(
+        &mut self,
+        slot: BindingSlot,
+        key: Option<KeyCode>,
+        button: Option<GamepadButton>,
+    ) -> Capture {
+        match (slot.is_gamepad(), key, button) {
+            (true, _, Some(button)) => Capture::Bound(self.set_button(slot, button)),
+            (false, Some(key), _) => Capture::Bound(self.set_key(slot, key)),
+            (_, None, None) => Capture::Waiting,
+            _ => Capture::Cancelled,
+        }
+    }
+
+    /// Bind `button` to `slot`, returns the slot which was already using it, like [`Self::set_key`]
+    pub fn set_button(&mut self, slot: BindingSlot, button: GamepadButton) -> Option<BindingSlot> {
+        let previous = self.button(slot)?;
+        let conflict = BindingSlot::ALL
+            .into_iter()
+            .find(|other| *other != slot && self.button(*other) == Some(button));
+
+        if let Some(other) = conflict {
+            *self.button_mut(other)? = previous;
+        }
+        *self.button_mut(slot)? = button;
+
+        conflict
+    }
+}
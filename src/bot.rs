@@ -0,0 +1,212 @@
+//! Scripted player for automated playtesting
+//!
+//! The bot paths on the navmesh toward the nearest target, grabbing the powerups close by on the way,
+//! and drives the [`Player`] by mocking its input actions, exactly like a human would.
+
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use bevy_landmass::{
+    Agent, Agent3dBundle, AgentDesiredVelocity3d, AgentSettings, AgentTarget3d, ArchipelagoRef3d,
+    TargetReachedCondition,
+};
+
+use crate::{
+    game::{AppState, GameState, PlayingState},
+    player::{
+        Jump, Movement, PLAYER_BOOST_SPEED, PLAYER_DEFAULT_SPEED, Player, PlayerCameraAnchorY,
+    },
+    powerup::Powerup,
+    target::Target,
+};
+
+/// Powerups further than this are not worth a detour
+const BOT_POWERUP_DETOUR: f32 = 25.0;
+/// How long the bot can be blocked before trying to jump
+const BOT_STUCK_TIME: f32 = 0.5;
+
+pub struct BotPlugin;
+
+impl Plugin for BotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BotReport>()
+            .add_systems(OnEnter(AppState::Playing), attach_agent)
+            .add_systems(
+                Update,
+                (pick_goal, drive)
+                    .chain()
+                    .run_if(in_state(PlayingState::Playing)),
+            )
+            .add_systems(OnExit(AppState::Playing), record_round);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BotRound {
+    pub difficulty: u8,
+    pub total_targets: u8,
+    pub aquired_targets: u8,
+    pub time_limit: f32,
+    pub elapsed: f32,
+}
+
+impl BotRound {
+    pub fn won(&self) -> bool {
+        self.aquired_targets == self.total_targets
+    }
+}
+
+/// Every round played by the bot
+#[derive(Resource, Default)]
+pub struct BotReport(pub Vec<BotRound>);
+
+impl BotReport {
+    /// One line per difficulty: rounds played, rounds won and average completion time of the won ones
+    pub fn summary(&self) -> String {
+        let max_difficulty = self
+            .0
+            .iter()
+            .map(|round| round.difficulty)
+            .max()
+            .unwrap_or(0);
+
+        (1..=max_difficulty)
+            .filter_map(|difficulty| {
+                let rounds = self
+                    .0
+                    .iter()
+                    .filter(|round| round.difficulty == difficulty)
+                    .collect::<Vec<_>>();
+                let won = rounds
+                    .iter()
+                    .filter(|round| round.won())
+                    .collect::<Vec<_>>();
+
+                let first = rounds.first()?;
+                let average = if won.is_empty() {
+                    "-".to_string()
+                } else {
+                    #[allow(clippy::cast_precision_loss)]
+                    let average =
+                        won.iter().map(|round| round.elapsed).sum::<f32>() / won.len() as f32;
+                    format!("{average:.1}s")
+                };
+
+                Some(format!(
+                    "difficulty {difficulty}: {} targets in {:.0}s, won {}/{}, average {average}",
+                    first.total_targets,
+                    first.time_limit,
+                    won.len(),
+                    rounds.len(),
+                ))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn attach_agent(
+    mut commands: Commands,
+    player: Single<Entity, With<Player>>,
+    navmesh: Single<Entity, With<bevy_landmass::Archipelago3d>>,
+) {
+    commands.entity(*player).insert((
+        Agent3dBundle {
+            agent: Agent::default(),
+            settings: AgentSettings {
+                radius: 0.5,
+                desired_speed: PLAYER_DEFAULT_SPEED,
+                max_speed: PLAYER_BOOST_SPEED,
+            },
+            archipelago_ref: ArchipelagoRef3d::new(*navmesh),
+        },
+        TargetReachedCondition::Distance(Some(0.5)),
+    ));
+}
+
+fn pick_goal(
+    mut commands: Commands,
+    player: Single<(Entity, &Transform, Option<&AgentTarget3d>), With<Player>>,
+    targets: Query<(Entity, &Transform), With<Target>>,
+    powerups: Query<(Entity, &Transform), With<Powerup>>,
+) {
+    let (entity, transform, current) = player.into_inner();
+
+    let nearest = |candidates: &mut dyn Iterator<Item = (Entity, &Transform)>| {
+        candidates
+            .map(|(candidate, candidate_transform)| {
+                (
+                    candidate,
+                    candidate_transform
+                        .translation
+                        .distance_squared(transform.translation),
+                )
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    };
+
+    let goal = nearest(&mut powerups.iter())
+        .filter(|(_, distance)| *distance < BOT_POWERUP_DETOUR * BOT_POWERUP_DETOUR)
+        .or_else(|| nearest(&mut targets.iter()))
+        .map(|(goal, _)| goal);
+
+    match (goal, current) {
+        (Some(goal), Some(AgentTarget3d::Entity(current))) if goal == *current => {}
+        (Some(goal), _) => {
+            commands.entity(entity).insert(AgentTarget3d::Entity(goal));
+        }
+        (None, _) => {
+            commands.entity(entity).remove::<AgentTarget3d>();
+        }
+    }
+}
+
+fn drive(
+    mut commands: Commands,
+    player: Single<(&AgentDesiredVelocity3d, &LinearVelocity), With<Player>>,
+    anchor: Single<&Transform, With<PlayerCameraAnchorY>>,
+    movement: Single<Entity, With<Action<Movement>>>,
+    jump: Single<Entity, With<Action<Jump>>>,
+    mut stuck: Local<f32>,
+    time: Res<Time>,
+) {
+    let (desired_vel, lin_vel) = player.into_inner();
+    let desired = desired_vel.velocity().with_y(0.0).normalize_or_zero();
+
+    // Inverse of the camera relative movement from `apply_movement`
+    let local = anchor.rotation.inverse() * desired;
+    let input = Vec2::new(-local.x, local.z);
+
+    let state = if input == Vec2::ZERO {
+        ActionState::None
+    } else {
+        ActionState::Fired
+    };
+    commands
+        .entity(*movement)
+        .insert(ActionMock::new(state, input, MockSpan::Manual));
+
+    // Jump over whatever is in the way
+    if desired != Vec3::ZERO && lin_vel.xz().length() < 1.0 {
+        *stuck += time.delta_secs();
+    } else {
+        *stuck = 0.0;
+    }
+
+    if *stuck > BOT_STUCK_TIME {
+        *stuck = 0.0;
+        commands
+            .entity(*jump)
+            .insert(ActionMock::once(ActionState::Fired, true));
+    }
+}
+
+fn record_round(mut report: ResMut<BotReport>, game_state: Res<GameState>) {
+    report.0.push(BotRound {
+        difficulty: game_state.difficulty,
+        total_targets: game_state.total_targets,
+        aquired_targets: game_state.aquired_targets,
+        time_limit: game_state.timer.duration().as_secs_f32(),
+        elapsed: game_state.timer.elapsed_secs(),
+    });
+}
@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_tweening::{Tween, TweenAnim, lens::UiTransformScaleLens};
+
+use crate::{
+    game::{AppState, PlayingState, SetupState},
+    loader::Fonts,
+};
+
+/// Time to catch the next target before the combo drops a level, in seconds
+const COMBO_WINDOW: f32 = 3.0;
+const MAX_MULTIPLIER: u32 = 5;
+/// Points for each second left on the timer when the round is won
+const TIME_BONUS_PER_SECOND: f32 = 10.0;
+
+pub struct ComboPlugin;
+
+impl Plugin for ComboPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Combo>()
+            .init_resource::<Combo>()
+            .add_systems(OnEnter(SetupState::Entities), (reset_combo, setup_ui))
+            .add_systems(
+                Update,
+                (decay_combo, update_ui)
+                    .chain()
+                    .run_if(in_state(PlayingState::Playing)),
+            );
+    }
+}
+
+/// Targets caught in quick succession
+#[derive(Resource, Reflect)]
+pub struct Combo {
+    pub streak: u32,
+    window: Timer,
+}
+
+impl Default for Combo {
+    fn default() -> Self {
+        Self {
+            streak: 0,
+            window: Timer::from_seconds(COMBO_WINDOW, TimerMode::Once),
+        }
+    }
+}
+
+impl Combo {
+    pub fn multiplier(&self) -> u32 {
+        self.streak.clamp(1, MAX_MULTIPLIER)
+    }
+
+    /// Count a catch and return the multiplier it gets
+    pub fn hit(&mut self) -> u32 {
+        self.streak = (self.streak + 1).min(MAX_MULTIPLIER);
+        self.window.reset();
+        self.multiplier()
+    }
+}
+
+/// Where the points of the run come from
+#[derive(Reflect, Clone, Copy, Debug, Default)]
+pub struct ScoreBreakdown {
+    /// Points of the targets themselves
+    pub base: u32,
+    /// Extra points from the combo multiplier
+    pub combo: u32,
+    /// Points for the time left at the end of the rounds
+    pub time_bonus: u32,
+}
+
+/// Bonus for the time left on the round timer
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn time_bonus(timer: &Timer) -> u32 {
+    (timer.remaining_secs() * TIME_BONUS_PER_SECOND).round() as u32
+}
+
+fn reset_combo(mut combo: ResMut<Combo>) {
+    *combo = Combo::default();
+}
+
+/// Drop a level each time the window runs out without a catch
+fn decay_combo(mut combo: ResMut<Combo>, time: Res<Time>) {
+    if combo.streak == 0 {
+        return;
+    }
+
+    combo.window.tick(time.delta());
+
+    if combo.window.is_finished() {
+        combo.streak -= 1;
+        combo.window.reset();
+    }
+}
+
+#[derive(Component)]
+struct ComboUi;
+
+fn setup_ui(mut commands: Commands, fonts: Res<Fonts>) {
+    // Next to the targets counter
+    commands.spawn((
+        GlobalZIndex(-1),
+        DespawnOnExit(AppState::Playing),
+        Node {
+            left: px(185),
+            top: px(25),
+            ..Default::default()
+        },
+        children![(
+            ComboUi,
+            Visibility::Hidden,
+            Node {
+                padding: UiRect::all(px(10)),
+                border_radius: BorderRadius::all(px(50)),
+                ..Default::default()
+            },
+            BackgroundColor(Color::linear_rgba(0.0, 0.0, 0.0, 0.8)),
+            Text::new(""),
+            TextFont {
+                font: fonts.blue_winter.clone(),
+                font_size: 36.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.95, 0.75, 0.1)),
+            TextShadow::default(),
+        )],
+    ));
+}
+
+fn update_ui(
+    mut commands: Commands,
+    ui: Single<(Entity, &mut Text, &mut Visibility), With<ComboUi>>,
+    combo: Res<Combo>,
+    mut last: Local<u32>,
+) {
+    let multiplier = combo.multiplier();
+    if multiplier == *last {
+        return;
+    }
+
+    let (entity, mut text, mut visibility) = ui.into_inner();
+    text.0 = format!("x{multiplier}");
+    *visibility = if multiplier > 1 {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    };
+
+    // Only pop when it goes up
+    if multiplier > *last {
+        commands.entity(entity).insert(TweenAnim::new(
+            Tween::new(
+                EaseFunction::BounceInOut,
+                Duration::from_secs_f32(0.25),
+                UiTransformScaleLens {
+                    start: Vec2::splat(1.5),
+                    end: Vec2::ONE,
+                },
+            )
+            .with_repeat_count(1),
+        ));
+    }
+
+    *last = multiplier;
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    flee::TARGET_AWARENESS_RADIUS,
+    target::{TARGET_DEFAULT_SPEED, TARGET_IDLE_TIMER},
+};
+
+/// Settings of a single round, missing fields use the defaults
+#[derive(Reflect, Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct DifficultyStep {
+    /// Number of targets to catch
+    pub targets: u8,
+    /// Time to catch them all, in seconds
+    pub duration: f32,
+    pub target_speed: f32,
+    /// How long targets stay idle once they reached their goal, in seconds
+    pub target_idle: f32,
+    /// Time between two powerups, in seconds
+    pub powerup_interval: f32,
+    /// Distance at which targets notice the player and flee
+    pub awareness_radius: f32,
+}
+
+impl Default for DifficultyStep {
+    fn default() -> Self {
+        Self {
+            targets: 10,
+            duration: 120.0,
+            target_speed: TARGET_DEFAULT_SPEED,
+            target_idle: TARGET_IDLE_TIMER,
+            powerup_interval: 15.0,
+            awareness_radius: TARGET_AWARENESS_RADIUS,
+        }
+    }
+}
+
+/// One [`DifficultyStep`] per round, the last one is repeated once the curve runs out
+///
+/// Levels can define their own in their `.level.ron`
+#[derive(Resource, Reflect, Clone, Debug, Deserialize)]
+#[serde(transparent)]
+pub struct DifficultyCurve(pub Vec<DifficultyStep>);
+
+impl Default for DifficultyCurve {
+    fn default() -> Self {
+        Self(
+            (1..=10u8)
+                .map(|difficulty| DifficultyStep {
+                    targets: 10 + (difficulty - 1) * 4,
+                    duration: (120.0 - f32::from(difficulty) * 10.0).max(30.0),
+                    ..default()
+                })
+                .collect(),
+        )
+    }
+}
+
+impl DifficultyCurve {
+    /// Step for the given difficulty, starting at 1
+    pub fn step(&self, difficulty: u8) -> DifficultyStep {
+        let index = usize::from(difficulty.max(1) - 1);
+
+        self.0
+            .get(index)
+            .or(self.0.last())
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Preset applied on top of the level [`DifficultyCurve`], selected from the main menu
+#[derive(Reflect, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DifficultyMode {
+    Relaxed,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl DifficultyMode {
+    pub fn next(self) -> Self {
+        match self {
+            Self::Relaxed => Self::Normal,
+            Self::Normal => Self::Hard,
+            Self::Hard => Self::Relaxed,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Relaxed => "Relaxed",
+            Self::Normal => "Normal",
+            Self::Hard => "Hard",
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn apply(self, step: DifficultyStep) -> DifficultyStep {
+        let (targets, duration, speed) = match self {
+            Self::Relaxed => (0.75, 1.5, 0.8),
+            Self::Normal => (1.0, 1.0, 1.0),
+            Self::Hard => (1.25, 0.8, 1.2),
+        };
+
+        DifficultyStep {
+            targets: (f32::from(step.targets) * targets)
+                .round()
+                .clamp(1.0, 255.0) as u8,
+            duration: step.duration * duration,
+            target_speed: step.target_speed * speed,
+            awareness_radius: step.awareness_radius * speed,
+            ..step
+        }
+    }
+}
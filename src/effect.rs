@@ -0,0 +1,324 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_tweening::{Tween, TweenAnim, lens::UiTransformScaleLens};
+
+use crate::{
+    game::{AppState, PlayingState, SetupState},
+    loader::Fonts,
+    physics::MovementAcceleration,
+    player::Player,
+    target::{Target, TargetSystems},
+};
+
+/// Remaining time under which the HUD flashes the effect, in seconds
+const EXPIRE_WARNING: f32 = 1.5;
+/// Flashes per second of the expiring effects
+const FLASH_RATE: f32 = 4.0;
+/// Length of the HUD rows animations, in seconds
+const ROW_TWEEN: f32 = 0.25;
+
+pub struct EffectPlugin;
+
+impl Plugin for EffectPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<StatusEffects>()
+            .add_systems(OnEnter(SetupState::Entities), setup_ui)
+            .add_systems(
+                Update,
+                (tick_effects, apply_modifiers, update_ui)
+                    .chain()
+                    .before(TargetSystems::Steer)
+                    .run_if(in_state(PlayingState::Playing)),
+            );
+    }
+}
+
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EffectKind {
+    Boost,
+    Magnet,
+    DoubleJump,
+    DoubleScore,
+    Frozen,
+}
+
+impl EffectKind {
+    fn icon(self) -> &'static str {
+        match self {
+            Self::Boost => ">>",
+            Self::Magnet => "U",
+            Self::DoubleJump => "^^",
+            Self::DoubleScore => "x2",
+            Self::Frozen => "*",
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Self::Boost => Color::srgb(0.95, 0.55, 0.1),
+            Self::Magnet => Color::srgb(0.85, 0.15, 0.2),
+            Self::DoubleJump => Color::srgb(0.3, 0.85, 0.3),
+            Self::DoubleScore => Color::srgb(0.95, 0.75, 0.1),
+            Self::Frozen => Color::srgb(0.4, 0.75, 0.95),
+        }
+    }
+}
+
+/// What happens when an effect is added while the same kind is already active
+#[derive(Reflect, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stacking {
+    /// Start the active one over
+    Refresh,
+    /// Add another one next to it, each with its own timer
+    Stack,
+    /// Keep the active one as is
+    Ignore,
+}
+
+/// Change to the stats of the entity while the effect is active
+#[derive(Reflect, Clone, Copy, Debug, PartialEq)]
+pub enum Modifier {
+    None,
+    /// Multiplies the movement speed
+    Speed(f32),
+}
+
+#[derive(Reflect, Clone, Debug)]
+pub struct StatusEffect {
+    pub kind: EffectKind,
+    pub modifier: Modifier,
+    pub stacking: Stacking,
+    pub timer: Timer,
+}
+
+impl StatusEffect {
+    pub fn new(kind: EffectKind, seconds: f32, modifier: Modifier, stacking: Stacking) -> Self {
+        Self {
+            kind,
+            modifier,
+            stacking,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+}
+
+/// Timed effects active on the entity, the stats are recomputed from them every frame
+#[derive(Component, Reflect, Default, Debug)]
+pub struct StatusEffects(Vec<StatusEffect>);
+
+impl StatusEffects {
+    pub fn add(&mut self, effect: StatusEffect) {
+        let active = self.0.iter_mut().find(|active| active.kind == effect.kind);
+
+        match (active, effect.stacking) {
+            (Some(active), Stacking::Refresh) => *active = effect,
+            (Some(_), Stacking::Ignore) => {}
+            (Some(_), Stacking::Stack) | (None, _) => self.0.push(effect),
+        }
+    }
+
+    pub fn has(&self, kind: EffectKind) -> bool {
+        self.0.iter().any(|effect| effect.kind == kind)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &StatusEffect> {
+        self.0.iter()
+    }
+
+    pub fn speed_factor(&self) -> f32 {
+        self.0
+            .iter()
+            .map(|effect| match effect.modifier {
+                Modifier::Speed(factor) => factor,
+                Modifier::None => 1.0,
+            })
+            .product()
+    }
+}
+
+fn tick_effects(query: Query<&mut StatusEffects>, time: Res<Time>) {
+    for mut effects in query {
+        for effect in &mut effects.0 {
+            effect.timer.tick(time.delta());
+        }
+
+        effects.0.retain(|effect| !effect.timer.is_finished());
+    }
+}
+
+/// Start from the base stats each frame, so expiring effects never clobber each other
+fn apply_modifiers(query: Query<(&StatusEffects, &mut MovementAcceleration)>) {
+    for (effects, mut acceleration) in query {
+        acceleration.target = acceleration.base * effects.speed_factor();
+    }
+}
+
+/// Column of the active effects, under the targets counter
+#[derive(Component)]
+struct EffectsUi;
+
+/// Icon and countdown of one kind of effect in [`EffectsUi`]
+#[derive(Component)]
+struct EffectRow {
+    kind: EffectKind,
+    bar: Entity,
+    /// Set while the row animates out, despawned once finished
+    leaving: Option<Timer>,
+}
+
+fn setup_ui(mut commands: Commands) {
+    commands.spawn((
+        EffectsUi,
+        GlobalZIndex(-1),
+        DespawnOnExit(AppState::Playing),
+        Node {
+            position_type: PositionType::Absolute,
+            left: px(25),
+            top: px(110),
+            flex_direction: FlexDirection::Column,
+            row_gap: px(8),
+            ..Default::default()
+        },
+    ));
+}
+
+/// Keep a row for each kind of effect active on the player or the targets
+fn update_ui(
+    mut commands: Commands,
+    panel: Single<Entity, With<EffectsUi>>,
+    player: Single<&StatusEffects, With<Player>>,
+    targets: Query<&StatusEffects, (With<Target>, Without<Player>)>,
+    mut rows: Query<(Entity, &mut EffectRow)>,
+    mut bars: Query<(&mut Node, &mut BackgroundColor)>,
+    fonts: Res<Fonts>,
+    time: Res<Time>,
+) {
+    // Longest remaining timer of each kind
+    let mut active: Vec<(EffectKind, &Timer)> = Vec::new();
+    for effect in player
+        .iter()
+        .chain(targets.iter().flat_map(StatusEffects::iter))
+    {
+        match active.iter_mut().find(|(kind, _)| *kind == effect.kind) {
+            Some((_, timer)) if timer.remaining() < effect.timer.remaining() => {
+                *timer = &effect.timer;
+            }
+            Some(_) => {}
+            None => active.push((effect.kind, &effect.timer)),
+        }
+    }
+
+    for (entity, mut row) in &mut rows {
+        if let Some(leaving) = &mut row.leaving {
+            leaving.tick(time.delta());
+            if leaving.is_finished() {
+                commands.entity(entity).despawn();
+            }
+            continue;
+        }
+
+        let Some(index) = active.iter().position(|(kind, _)| *kind == row.kind) else {
+            row.leaving = Some(Timer::from_seconds(ROW_TWEEN, TimerMode::Once));
+            commands.entity(entity).insert(TweenAnim::new(Tween::new(
+                EaseFunction::BackIn,
+                Duration::from_secs_f32(ROW_TWEEN),
+                UiTransformScaleLens {
+                    start: Vec2::ONE,
+                    end: Vec2::ZERO,
+                },
+            )));
+            continue;
+        };
+
+        let (kind, timer) = active.swap_remove(index);
+        if let Ok((mut node, mut color)) = bars.get_mut(row.bar) {
+            node.width = percent(timer.fraction_remaining() * 100.0);
+
+            let flash = timer.remaining_secs() < EXPIRE_WARNING
+                && (timer.remaining_secs() * FLASH_RATE).fract() < 0.5;
+            color.0 = if flash { Color::WHITE } else { kind.color() };
+        }
+    }
+
+    for (kind, _) in active {
+        spawn_row(&mut commands, *panel, kind, &fonts);
+    }
+}
+
+fn spawn_row(commands: &mut Commands, panel: Entity, kind: EffectKind, fonts: &Fonts) {
+    let row = commands
+        .spawn((
+            ChildOf(panel),
+            Node {
+                padding: UiRect::all(px(6)),
+                column_gap: px(10),
+                align_items: AlignItems::Center,
+                border_radius: BorderRadius::all(px(50)),
+                ..Default::default()
+            },
+            BackgroundColor(Color::linear_rgba(0.0, 0.0, 0.0, 0.8)),
+            TweenAnim::new(Tween::new(
+                EaseFunction::BackOut,
+                Duration::from_secs_f32(ROW_TWEEN),
+                UiTransformScaleLens {
+                    start: Vec2::ZERO,
+                    end: Vec2::ONE,
+                },
+            )),
+            children![(
+                Node {
+                    width: px(36),
+                    height: px(36),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    border_radius: BorderRadius::all(px(18)),
+                    ..Default::default()
+                },
+                BackgroundColor(kind.color()),
+                children![(
+                    Text::new(kind.icon()),
+                    TextFont {
+                        font: fonts.blue_winter.clone(),
+                        font_size: 20.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.1, 0.1, 0.1)),
+                )],
+            )],
+        ))
+        .id();
+
+    let track = commands
+        .spawn((
+            ChildOf(row),
+            Node {
+                width: px(100),
+                height: px(12),
+                margin: UiRect::right(px(6)),
+                border_radius: BorderRadius::all(px(6)),
+                ..Default::default()
+            },
+            BackgroundColor(Color::linear_rgba(0.2, 0.2, 0.2, 0.8)),
+        ))
+        .id();
+
+    let bar = commands
+        .spawn((
+            ChildOf(track),
+            Node {
+                width: percent(100),
+                height: percent(100),
+                border_radius: BorderRadius::all(px(6)),
+                ..Default::default()
+            },
+            BackgroundColor(kind.color()),
+        ))
+        .id();
+
+    commands.entity(row).insert(EffectRow {
+        kind,
+        bar,
+        leaving: None,
+    });
+}
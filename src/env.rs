@@ -19,10 +19,15 @@ use landmass_rerecast::{Island3dBundle, LandmassRerecastPlugin, NavMeshHandle3d}
 use crate::{
     game::{AppState, SetupState},
     loader::{LevelAssetHandles, LevelDef},
+    minimap::MinimapPlugin,
 };
 
 pub struct EnvironmentPlugin;
 
+/// Static colliders of the level, blocking the line of sight of the targets
+#[derive(Component)]
+pub struct EnvironmentCollider;
+
 impl Plugin for EnvironmentPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins((
@@ -30,6 +35,7 @@ impl Plugin for EnvironmentPlugin {
             AvianBackendPlugin::default(),
             Landmass3dPlugin::default(),
             LandmassRerecastPlugin::default(),
+            MinimapPlugin,
         ))
         .add_systems(OnEnter(SetupState::Environment), setup)
         .add_systems(
@@ -133,11 +139,15 @@ fn setup(
                 ));
 
                 if col_tri {
-                    entity.insert(
+                    entity.insert((
                         Collider::trimesh_from_mesh_with_config(mesh, TrimeshFlags::all()).unwrap(),
-                    );
+                        EnvironmentCollider,
+                    ));
                 } else if col {
-                    entity.insert(Collider::convex_decomposition_from_mesh(mesh).unwrap());
+                    entity.insert((
+                        Collider::convex_decomposition_from_mesh(mesh).unwrap(),
+                        EnvironmentCollider,
+                    ));
                 }
             }
         });
@@ -148,15 +158,16 @@ fn setup(
         DespawnOnExit(AppState::Playing),
         Name::new("God"),
         SceneRoot(handles.god.clone()),
+        level_def.god_behavior,
     ));
 
     // Lights
     // info!("Spawning lights");
-    commands.insert_resource(level_def.ambient_light.clone());
+    commands.insert_resource(GlobalAmbientLight::from(&level_def.ambient_light));
 
     commands.spawn((
-        level_def.directional_light,
-        level_def.directional_light_transform,
+        level_def.directional_light.light(),
+        level_def.directional_light.transform(),
         CascadeShadowConfigBuilder {
             first_cascade_far_bound: 200.0,
             maximum_distance: 400.0,
@@ -0,0 +1,179 @@
+use avian3d::prelude::*;
+use bevy::prelude::*;
+use bevy_landmass::{AgentTarget3d, Archipelago3d};
+
+use crate::{
+    env::EnvironmentCollider,
+    game::{GameState, sample_position_on_navmesh},
+    player::Player,
+    target::{IdleTimer, RequestNewTarget, Target, TargetSystems},
+};
+
+pub const TARGET_AWARENESS_RADIUS: f32 = 20.0;
+/// Fleeing targets feel safe once the player is that much further than the awareness radius
+const SAFE_RADIUS_FACTOR: f32 = 1.5;
+/// How long targets need to feel safe before wandering again, in seconds
+const CALM_DOWN_TIMER: f32 = 2.0;
+/// How often fleeing targets look for a better escape, in seconds
+const FLEE_REPATH_TIMER: f32 = 0.5;
+/// How far targets run on each escape
+const FLEE_DISTANCE: f32 = 25.0;
+/// Shorter escapes tried along each direction when the full one is out of reach
+const FLEE_REACH: [f32; 3] = [1.0, 0.6, 0.3];
+/// Height of the target eyes, and of the spot they look at on the player
+const EYE_HEIGHT: f32 = 0.5;
+
+pub struct FleePlugin;
+
+impl Plugin for FleePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Fleeing>().add_systems(
+            Update,
+            (perceive_player, flee)
+                .chain()
+                .in_set(TargetSystems::Perceive),
+        );
+    }
+}
+
+/// The target saw the player and runs away from it
+#[derive(Component, Reflect)]
+pub struct Fleeing {
+    calm: Timer,
+    repath: Timer,
+}
+
+impl Default for Fleeing {
+    fn default() -> Self {
+        let mut repath = Timer::from_seconds(FLEE_REPATH_TIMER, TimerMode::Repeating);
+        // Pick an escape right away
+        repath.almost_finish();
+
+        Self {
+            calm: Timer::from_seconds(CALM_DOWN_TIMER, TimerMode::Once),
+            repath,
+        }
+    }
+}
+
+fn perceive_player(
+    mut commands: Commands,
+    targets: Query<(Entity, &Transform, Option<&mut Fleeing>), With<Target>>,
+    player: Single<&Transform, With<Player>>,
+    environment: Query<(), With<EnvironmentCollider>>,
+    spatial_query: SpatialQuery,
+    game_state: Res<GameState>,
+    time: Res<Time>,
+) {
+    let radius = game_state.step.awareness_radius;
+    let eye = player.translation + Vec3::Y * EYE_HEIGHT;
+
+    for (entity, transform, fleeing) in targets {
+        let distance = transform.translation.distance(player.translation);
+
+        match fleeing {
+            Some(mut fleeing) => {
+                // Once scared, the target keeps track of the player even out of sight
+                if distance < radius * SAFE_RADIUS_FACTOR {
+                    fleeing.calm.reset();
+                    continue;
+                }
+
+                fleeing.calm.tick(time.delta());
+
+                if fleeing.calm.is_finished() {
+                    commands
+                        .entity(entity)
+                        .remove::<Fleeing>()
+                        .insert(RequestNewTarget);
+                }
+            }
+            None => {
+                if distance > radius {
+                    continue;
+                }
+
+                let origin = transform.translation + Vec3::Y * EYE_HEIGHT;
+                let Ok(direction) = Dir3::new(eye - origin) else {
+                    continue;
+                };
+
+                let blocked = spatial_query
+                    .cast_ray_predicate(
+                        origin,
+                        direction,
+                        origin.distance(eye),
+                        true,
+                        &SpatialQueryFilter::default(),
+                        &|hit| environment.contains(hit),
+                    )
+                    .is_some();
+
+                if !blocked {
+                    // info!("{entity} saw the player");
+                    commands
+                        .entity(entity)
+                        .remove::<(IdleTimer, RequestNewTarget)>()
+                        .insert(Fleeing::default());
+                }
+            }
+        }
+    }
+}
+
+/// Send fleeing targets toward the reachable point furthest from the player
+fn flee(
+    mut commands: Commands,
+    targets: Query<(Entity, &Transform, &mut Fleeing)>,
+    player: Single<&Transform, With<Player>>,
+    navmesh: Single<&Archipelago3d>,
+    time: Res<Time>,
+) {
+    for (entity, transform, mut fleeing) in targets {
+        fleeing.repath.tick(time.delta());
+
+        if !fleeing.repath.just_finished() {
+            continue;
+        }
+
+        let Ok(start) = sample_position_on_navmesh(transform.translation, 5.0, &navmesh) else {
+            warn!("{entity} is off the navmesh, it can't flee");
+            continue;
+        };
+
+        let away = (transform.translation - player.translation)
+            .with_y(0.0)
+            .normalize_or(Vec3::X);
+
+        // Fan out away from the player, walls, corners and other islands rule some escapes out
+        let escape = [0.0f32, 30.0, -30.0, 60.0, -60.0, 90.0, -90.0]
+            .into_iter()
+            .filter_map(|angle| {
+                let direction = Quat::from_rotation_y(angle.to_radians()) * away;
+
+                // The furthest reachable escape along that direction
+                FLEE_REACH.into_iter().find_map(|reach| {
+                    let distance = reach * FLEE_DISTANCE;
+                    sample_position_on_navmesh(
+                        transform.translation + direction * distance,
+                        distance * 0.5,
+                        &navmesh,
+                    )
+                    .ok()
+                    .filter(|point| navmesh.find_path(&start, point, &default()).is_ok())
+                    .map(|point| point.point())
+                })
+            })
+            .max_by(|a, b| {
+                a.distance_squared(player.translation)
+                    .total_cmp(&b.distance_squared(player.translation))
+            });
+
+        match escape {
+            Some(point) => {
+                commands.entity(entity).insert(AgentTarget3d::Point(point));
+            }
+            None => warn!("{entity} has nowhere to flee"),
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+/// Boids-style steering among nearby targets, layered on top of their navmesh steering
+///
+/// Set per level with `flocking` in the `.level.ron`
+#[derive(Resource, Reflect, Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct Flocking {
+    /// Distance under which targets influence each other
+    pub radius: f32,
+    /// Keep some room between targets
+    pub separation: f32,
+    /// Head the same way as the neighbors
+    pub alignment: f32,
+    /// Move toward the center of the neighbors
+    pub cohesion: f32,
+}
+
+impl Default for Flocking {
+    fn default() -> Self {
+        Self {
+            radius: 6.0,
+            separation: 1.0,
+            alignment: 0.3,
+            cohesion: 0.1,
+        }
+    }
+}
+
+impl Flocking {
+    /// Steering to add to the heading of the target at `position`, from the position and
+    /// heading of every target, itself included
+    pub fn steer(&self, position: Vec3, neighbors: &[(Vec3, Vec3)]) -> Vec3 {
+        let mut separation = Vec3::ZERO;
+        let mut heading = Vec3::ZERO;
+        let mut center = Vec3::ZERO;
+        let mut count = 0u16;
+
+        for (other, other_heading) in neighbors {
+            let offset = (position - *other).with_y(0.0);
+            let distance = offset.length();
+
+            // Skip itself, and the targets too far away to matter
+            if distance < f32::EPSILON || distance > self.radius {
+                continue;
+            }
+
+            // Push harder the closer they are
+            separation += offset / distance * (1.0 - distance / self.radius);
+            heading += *other_heading;
+            center += *other;
+            count += 1;
+        }
+
+        if count == 0 {
+            return Vec3::ZERO;
+        }
+
+        let count = f32::from(count);
+        let alignment = (heading / count).with_y(0.0);
+        let cohesion = (center / count - position).with_y(0.0) / self.radius;
+
+        separation * self.separation + alignment * self.alignment + cohesion * self.cohesion
+    }
+}
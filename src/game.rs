@@ -2,13 +2,23 @@ use std::time::Duration;
 
 use bevy::{prelude::*, text::LineHeight, time::common_conditions::on_timer};
 use bevy_tweening::{AnimTarget, Lens, Tween, TweenAnim, lens::UiTransformScaleLens};
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    bindings::InputBindings,
+    combo::{Combo, ComboPlugin, ScoreBreakdown, time_bonus},
+    difficulty::{DifficultyCurve, DifficultyMode, DifficultyStep},
+    effect::{EffectKind, EffectPlugin, StatusEffects},
+    indicator::IndicatorPlugin,
     loader::{Fonts, LevelAssetHandles, LevelDef, PermanentAssetHandles},
-    physics::{MovementAcceleration, PlayerHitPowerup, PlayerHitTarget},
-    player::{PLAYER_BOOST_SPEED, Player},
-    powerup::{PowerupBundle, PowerupTimer},
-    target::TargetBundle,
+    physics::{PlayerHitPowerup, PlayerHitTarget},
+    player::Player,
+    powerup::{PowerupBundle, PowerupKind, TIME_EXTENSION},
+    seed::{PowerupRng, RunSeed, TargetRng},
+    shuffle::Shuffle,
+    storage,
+    target::{Target, TargetBehaviors, TargetBundle, TargetScore},
     transition::{ContinueTransition, StartTransition, TransitionEnded, TransitionReachedMiddle},
 };
 
@@ -16,14 +26,16 @@ pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
-        app.init_state::<AppState>()
+        app.add_plugins((ComboPlugin, EffectPlugin, IndicatorPlugin))
+            .init_state::<AppState>()
             .register_type::<GameState>()
             .add_sub_state::<MenuState>()
             .add_sub_state::<LoadingState>()
             .add_sub_state::<SetupState>()
             .add_sub_state::<PlayingState>()
             .init_resource::<GameState>()
-            .init_resource::<GameSettings>()
+            .insert_resource(GameSettings::load())
+            .init_resource::<DifficultyCurve>()
             .add_systems(
                 OnTransition {
                     exited: AppState::MainMenu,
@@ -38,7 +50,7 @@ impl Plugin for GamePlugin {
                 (
                     tick_timer,
                     update_ui.run_if(on_timer(Duration::from_millis(100))),
-                    spawn_powerup.run_if(on_timer(Duration::from_secs(15))),
+                    spawn_powerup,
                 )
                     .run_if(in_state(PlayingState::Playing)),
             )
@@ -48,7 +60,17 @@ impl Plugin for GamePlugin {
             }, fade_goal_text)
             .add_systems(OnEnter(PlayingState::Starting), continue_load_transition)
             .add_systems(OnEnter(PlayingState::GameOver), game_over)
-            .add_systems(PostUpdate, despawn_later)
+            .add_systems(
+                PostUpdate,
+                (
+                    despawn_later,
+                    save_settings.run_if(
+                        resource_changed::<GameSettings>
+                            .and(not(resource_added::<GameSettings>))
+                            .and(not(resource_exists::<storage::NoStorage>)),
+                    ),
+                ),
+            )
             .add_observer(on_player_hit_powerup)
             .add_observer(on_player_hit_target)
             .add_observer(transition_in_finished)
@@ -95,6 +117,7 @@ pub enum MenuState {
     #[default]
     Main,
     Settings,
+    Controls,
 }
 
 #[derive(Clone, Debug, Default, PartialEq, Eq, Hash, SubStates)]
@@ -123,15 +146,21 @@ pub enum PlayingState {
     Playing,
     Paused,
     SettingsMenu,
+    ControlsMenu,
     GameOver,
 }
 
-#[derive(Resource, Clone, Copy)]
+#[derive(Resource, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameSettings {
     pub camera_x_sensitivity: f32,
     pub camera_y_sensitivity: f32,
     pub music_volume: f32,
     pub sfx_volume: f32,
+    pub difficulty_mode: DifficultyMode,
+    /// Distance of the camera at the start of a round, kept within the limits of the level
+    pub camera_distance: f32,
+    pub bindings: InputBindings,
 }
 
 impl Default for GameSettings {
@@ -141,11 +170,40 @@ impl Default for GameSettings {
             camera_y_sensitivity: 1.0,
             music_volume: 50.0,
             sfx_volume: 50.0,
+            difficulty_mode: DifficultyMode::Normal,
+            camera_distance: 20.0,
+            bindings: InputBindings::default(),
         }
     }
 }
 
 impl GameSettings {
+    const STORAGE_KEY: &str = "settings";
+    const STORAGE_VERSION: u32 = 1;
+
+    /// Load the saved settings, or the defaults if there are none
+    pub fn load() -> Self {
+        storage::load::<Self>(Self::STORAGE_KEY, Self::STORAGE_VERSION)
+            .map(|settings| settings.clamped())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::STORAGE_KEY, Self::STORAGE_VERSION, self);
+    }
+
+    /// Keep every setting within its allowed range
+    pub fn clamped(&self) -> Self {
+        Self {
+            camera_x_sensitivity: self.camera_x_sensitivity.clamp(0.0, 2.0),
+            camera_y_sensitivity: self.camera_y_sensitivity.clamp(0.0, 2.0),
+            music_volume: self.music_volume.clamp(0.0, 100.0),
+            sfx_volume: self.sfx_volume.clamp(0.0, 100.0),
+            camera_distance: self.camera_distance.clamp(5.0, 50.0),
+            ..*self
+        }
+    }
+
     pub fn cam_x(&self, v: f32) -> Self {
         Self {
             camera_x_sensitivity: self.camera_x_sensitivity + v,
@@ -160,6 +218,30 @@ impl GameSettings {
         }
     }
 
+    pub fn zoom(&self, v: f32) -> Self {
+        Self {
+            camera_distance: self.camera_distance + v,
+            ..*self
+        }
+    }
+
+    pub fn default_bindings(&self) -> Self {
+        Self {
+            bindings: InputBindings::default(),
+            ..*self
+        }
+    }
+
+    pub fn swap_sticks(&self) -> Self {
+        Self {
+            bindings: InputBindings {
+                swap_sticks: !self.bindings.swap_sticks,
+                ..self.bindings
+            },
+            ..*self
+        }
+    }
+
     pub fn music(&self, v: f32) -> Self {
         Self {
             music_volume: self.music_volume + v,
@@ -178,8 +260,11 @@ impl GameSettings {
 #[derive(Debug, Resource, Reflect)]
 pub struct GameState {
     pub difficulty: u8,
+    pub step: DifficultyStep,
     pub score: u32,
+    pub breakdown: ScoreBreakdown,
     pub timer: Timer,
+    pub powerup_timer: Timer,
     pub total_targets: u8,
     pub aquired_targets: u8,
 }
@@ -188,8 +273,11 @@ impl FromWorld for GameState {
     fn from_world(_world: &mut World) -> Self {
         GameState {
             difficulty: 0,
+            step: DifficultyStep::default(),
             score: 0,
+            breakdown: ScoreBreakdown::default(),
             timer: Timer::from_seconds(120.0, TimerMode::Once),
+            powerup_timer: Timer::from_seconds(15.0, TimerMode::Repeating),
             total_targets: 0,
             aquired_targets: 0,
         }
@@ -197,23 +285,54 @@ impl FromWorld for GameState {
 }
 
 impl GameState {
-    fn next_difficulty(&mut self) {
+    fn next_difficulty(&mut self, curve: &DifficultyCurve, mode: DifficultyMode) {
         self.difficulty += 1;
-        self.total_targets = 10 + (self.difficulty - 1) * 4;
+        self.step = mode.apply(curve.step(self.difficulty));
+        self.total_targets = self.step.targets;
         self.aquired_targets = 0;
 
-        let new_duration = (120 - (u64::from(self.difficulty) * 10)).max(30);
-
         self.timer
-            .set_duration(std::time::Duration::from_secs(new_duration));
+            .set_duration(Duration::from_secs_f32(self.step.duration));
         self.timer.reset();
+        self.powerup_timer
+            .set_duration(Duration::from_secs_f32(self.step.powerup_interval));
+        self.powerup_timer.reset();
     }
 }
 
-fn reset_game(mut commands: Commands) {
+fn save_settings(settings: Res<GameSettings>) {
+    settings.save();
+}
+
+fn reset_game(
+    mut commands: Commands,
+    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<bevy_rand::global::GlobalRng>>,
+    mut target_rng: Single<
+        &mut bevy_prng::ChaCha20Rng,
+        (With<TargetRng>, Without<bevy_rand::global::GlobalRng>),
+    >,
+    mut powerup_rng: Single<
+        &mut bevy_prng::ChaCha20Rng,
+        (
+            With<PowerupRng>,
+            Without<TargetRng>,
+            Without<bevy_rand::global::GlobalRng>,
+        ),
+    >,
+    level_shuffle: Option<ResMut<Shuffle<LevelDef>>>,
+    seed: Res<RunSeed>,
+) {
     // info!("Resetting game");
     commands.remove_resource::<GameState>();
     commands.init_resource::<GameState>();
+
+    // Same seed, same run
+    **rng = seed.rng();
+    **target_rng = TargetRng::fork(&mut rng);
+    **powerup_rng = PowerupRng::fork(&mut rng);
+    if let Some(mut level_shuffle) = level_shuffle {
+        level_shuffle.reset();
+    }
 }
 
 fn init_game(
@@ -223,20 +342,29 @@ fn init_game(
     mut state: ResMut<GameState>,
     level_def: Res<LevelDef>,
     handles: Res<LevelAssetHandles>,
+    curve: Res<DifficultyCurve>,
+    settings: Res<GameSettings>,
+    behaviors: Res<TargetBehaviors>,
     mut next_state: ResMut<NextState<SetupState>>,
 ) {
     // info!("Picking difficulty");
-    state.next_difficulty();
+    state.next_difficulty(&curve, settings.difficulty_mode);
 
     // Spawn targets
     // info!("Spawning targets:");
+    let mut spawned = 0;
     for i in 0..state.total_targets {
         // info!("\t Target {}", i);
         let mut iter = 0;
         let mut pos = Err(bevy_landmass::SamplePointError::OutOfRange);
         while pos.is_err() && iter < 100 {
             iter += 1;
-            pos = get_random_position_on_navmesh(Vec3::ZERO, 120.0, navmesh.1, &mut rng);
+            pos = get_random_position_on_navmesh(
+                level_def.spawn_area.center,
+                level_def.spawn_area.radius,
+                navmesh.1,
+                &mut rng,
+            );
         }
 
         let pos = match pos {
@@ -247,18 +375,30 @@ fn init_game(
             }
         };
 
+        let Ok((variant, model)) = handles
+            .targets
+            .choose_weighted(&mut **rng, |(variant, _)| variant.weight)
+        else {
+            warn!("Could not pick a target variant for {}", level_def.prefix);
+            break;
+        };
+
         // info!("\t Target {} spawned!", i);
-        commands.spawn((
-            TargetBundle::new(
-                handles.target.clone(),
-                // handles.material.clone(),
-                pos.point(),
-                navmesh.0,
-            ),
-            level_def.target_behavior,
+        let mut target = commands.spawn(TargetBundle::new(
+            model.clone(),
+            // handles.material.clone(),
+            pos.point(),
+            navmesh.0,
+            &state.step,
+            variant,
         ));
+        behaviors.insert(&level_def.target_behavior, &mut target);
+        spawned += 1;
     }
 
+    // The round is won once every spawned target is caught
+    state.total_targets = spawned;
+
     next_state.set(SetupState::Animation);
 }
 
@@ -444,7 +584,7 @@ fn setup_ui(mut commands: Commands, fonts: Res<Fonts>, level_def: Res<LevelDef>)
                     },
                     children![(
                         GoalUi,
-                        Text::new(level_def.goal),
+                        Text::new(level_def.goal.clone()),
                         TextFont {
                             font: fonts.blue_winter.clone(),
                             font_size: 56.0,
@@ -552,27 +692,67 @@ fn tick_timer(
 fn on_player_hit_powerup(
     trigger: On<PlayerHitPowerup>,
     mut commands: Commands,
-    mut player: Single<(Entity, &mut MovementAcceleration), With<Player>>,
+    mut player: Single<&mut StatusEffects, With<Player>>,
+    mut targets: Query<&mut StatusEffects, (With<Target>, Without<Player>)>,
+    powerups: Query<&PowerupKind>,
+    mut game_state: ResMut<GameState>,
 ) {
     commands.entity(trigger.0).insert(DespawnLaterPlease);
 
-    commands.entity(player.0).insert(PowerupTimer::default());
-    player.1.target = PLAYER_BOOST_SPEED;
+    let Ok(&kind) = powerups.get(trigger.0) else {
+        return;
+    };
+
+    let Some(effect) = kind.effect() else {
+        // Time is the only instant one
+        let elapsed = game_state
+            .timer
+            .elapsed()
+            .saturating_sub(Duration::from_secs_f32(TIME_EXTENSION));
+        game_state.timer.set_elapsed(elapsed);
+        return;
+    };
+
+    if kind == PowerupKind::Freeze {
+        for mut effects in &mut targets {
+            effects.add(effect.clone());
+        }
+    } else {
+        player.add(effect);
+    }
 }
 
 fn on_player_hit_target(
     trigger: On<PlayerHitTarget>,
     mut commands: Commands,
     mut game_state: ResMut<GameState>,
+    scores: Query<&TargetScore>,
+    effects: Single<&StatusEffects, With<Player>>,
+    mut combo: ResMut<Combo>,
     mut next_state: ResMut<NextState<AppState>>,
 ) {
     commands.entity(trigger.0).insert(DespawnLaterPlease);
 
+    // Double score effects stack
+    let base = scores.get(trigger.0).map_or(100, |score| score.0)
+        * effects
+            .iter()
+            .filter(|effect| effect.kind == EffectKind::DoubleScore)
+            .map(|_| 2)
+            .product::<u32>();
+    let bonus = base * (combo.hit() - 1);
+
     game_state.aquired_targets += 1;
-    game_state.score += 100;
+    game_state.breakdown.base += base;
+    game_state.breakdown.combo += bonus;
+    game_state.score += base + bonus;
 
     if game_state.aquired_targets == game_state.total_targets {
         // info!("Player won the round!");
+        let bonus = time_bonus(&game_state.timer);
+        game_state.breakdown.time_bonus += bonus;
+        game_state.score += bonus;
+
         next_state.set(AppState::Loading);
     }
 }
@@ -586,9 +766,17 @@ fn spawn_powerup(
     mut commands: Commands,
     player: Single<&Transform, With<Player>>,
     navmesh: Single<(Entity, &bevy_landmass::Archipelago3d)>,
-    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<bevy_rand::global::GlobalRng>>,
+    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<PowerupRng>>,
     permanent_handles: Res<PermanentAssetHandles>,
+    level_def: Res<LevelDef>,
+    mut game_state: ResMut<GameState>,
+    time: Res<Time>,
 ) {
+    game_state.powerup_timer.tick(time.delta());
+    if !game_state.powerup_timer.just_finished() {
+        return;
+    }
+
     let mut iter = 0;
     let mut pos = Err(bevy_landmass::SamplePointError::OutOfRange);
     while pos.is_err() && iter < 100 {
@@ -604,9 +792,23 @@ fn spawn_powerup(
         }
     };
 
+    let Ok(chance) = level_def
+        .powerups
+        .choose_weighted(&mut **rng, |chance| chance.weight)
+    else {
+        warn!("Level {} has no powerups to spawn", level_def.prefix);
+        return;
+    };
+
+    let Some(model) = permanent_handles.powerup_models.get(&chance.kind) else {
+        warn!("No model loaded for the {:?} powerup", chance.kind);
+        return;
+    };
+
     // info!("\t Powerup spawned!");
     commands.spawn(PowerupBundle::new(
-        SceneRoot(permanent_handles.cheese.clone()),
+        chance.kind,
+        SceneRoot(model.clone()),
         pos.point(),
         Name::new("Powerup"),
     ));
@@ -624,10 +826,22 @@ pub fn get_random_position_on_navmesh<'a>(
     let circle = Circle { radius };
     let new_pos = circle.sample_interior(rng).extend(0.0).xzy() + center;
 
+    sample_position_on_navmesh(new_pos, radius * 0.5, navmesh)
+}
+
+/// Closest point on the navmesh, searching up to `horizontal_distance` away
+pub fn sample_position_on_navmesh(
+    position: Vec3,
+    horizontal_distance: f32,
+    navmesh: &bevy_landmass::Archipelago3d,
+) -> Result<
+    bevy_landmass::SampledPoint<'_, bevy_landmass::coords::ThreeD>,
+    bevy_landmass::SamplePointError,
+> {
     navmesh.sample_point(
-        new_pos,
+        position,
         &bevy_landmass::PointSampleDistance3d {
-            horizontal_distance: radius * 0.5,
+            horizontal_distance,
             distance_above: 20.0,
             distance_below: 20.0,
             vertical_preference_ratio: -5.0,
@@ -1,7 +1,26 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-#[derive(Component, Clone, Copy)]
+#[derive(Component, Clone, Copy, Deserialize)]
 pub enum GodBehavior {
     Cat,
     Necromencer,
 }
+
+impl GodBehavior {
+    /// Insert the tag of the behavior on the god bone
+    pub fn insert(self, entity: &mut EntityCommands) {
+        match self {
+            Self::Cat => entity.insert(CatGod),
+            Self::Necromencer => entity.insert(NecromancerGod),
+        };
+    }
+}
+
+/// The god stares at the player
+#[derive(Component)]
+pub struct CatGod;
+
+/// The god slowly turns toward the player while swaying
+#[derive(Component)]
+pub struct NecromancerGod;
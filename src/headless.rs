@@ -0,0 +1,223 @@
+//! Headless app for gameplay tests and simulations, no window, GPU or audio needed.
+//!
+//! The gameplay plugins run as is, everything which needs rendering or assets
+//! (loading, environment, transitions, animations) is replaced by a stub moving the states along.
+
+use std::{sync::Arc, time::Duration};
+
+use avian3d::prelude::*;
+use bevy::{
+    mesh::MeshPlugin, prelude::*, scene::ScenePlugin, state::app::StatesPlugin,
+    time::TimeUpdateStrategy,
+};
+use bevy_landmass::{
+    Archipelago3d, ArchipelagoOptions, ArchipelagoRef3d, FromAgentRadius, Island, Island3dBundle,
+    Landmass3dPlugin, NavMesh3d, NavMeshHandle3d, NavigationMesh3d, PointSampleDistance3d,
+};
+
+use crate::{
+    difficulty::DifficultyCurve,
+    env::EnvironmentCollider,
+    game::{AppState, GamePlugin, GameSettings, LoadingState, SetupState},
+    god::GodBehavior,
+    indicator::TargetIndicators,
+    loader::{
+        AmbientLightDef, DirectionalLightDef, Fonts, LevelAssetHandles, LevelDef,
+        PermanentAssetHandles,
+    },
+    mice::{Mice, MicePlugin},
+    physics::PhysicsPlugin,
+    player::CameraDistance,
+    seed::SeedPlugin,
+    shuffle::Shuffle,
+    storage::NoStorage,
+    target::{SpawnArea, TargetBehavior, TargetPlugin, TargetVariant},
+    transition::{ContinueTransition, StartTransition, TransitionEnded, TransitionReachedMiddle},
+};
+
+/// Half the size of the stub navmesh square
+pub const STUB_LEVEL_HALF_SIZE: f32 = 120.0;
+
+/// Build an app running the game state machine without rendering
+///
+/// Every update advances the time by `step`, so a whole round can be simulated in a few seconds.
+pub fn headless_app(step: Duration) -> App {
+    let mut app = App::new();
+
+    app.add_plugins((
+        MinimalPlugins,
+        StatesPlugin,
+        AssetPlugin::default(),
+        TransformPlugin,
+        MeshPlugin,
+        ScenePlugin,
+        bevy_rand::prelude::EntropyPlugin::<bevy_prng::ChaCha20Rng>::default(),
+        SeedPlugin,
+        Landmass3dPlugin::default(),
+        PhysicsPlugin,
+    ))
+    .add_plugins((TargetPlugin, MicePlugin, GamePlugin, HeadlessPlugin))
+    // Same settings on every machine, and nothing saved over the player's own
+    .insert_resource(GameSettings::default())
+    .insert_resource(NoStorage)
+    .insert_resource(TimeUpdateStrategy::ManualDuration(step));
+
+    app
+}
+
+/// Stubs for the parts of the game which need a window or assets
+pub struct HeadlessPlugin;
+
+impl Plugin for HeadlessPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Fonts {
+            blue_winter: Handle::default(),
+        })
+        .insert_resource(stub_level())
+        .add_systems(OnEnter(LoadingState::Loading), stub_load)
+        .add_systems(OnEnter(SetupState::Environment), stub_environment)
+        .add_systems(
+            Update,
+            wait_for_navmesh.run_if(in_state(SetupState::Environment)),
+        )
+        .add_systems(OnEnter(SetupState::Animation), stub_animation)
+        .add_observer(stub_transition_start)
+        .add_observer(stub_transition_continue);
+    }
+}
+
+/// A flat level, without music nor models
+pub fn stub_level() -> LevelDef {
+    LevelDef {
+        prefix: "headless".to_string(),
+        assets: None,
+        goal: "Headless".to_string(),
+        target_behavior: Mice::NAME.to_string(),
+        god_behavior: GodBehavior::Cat,
+        musics: Vec::new(),
+        ambient_light: AmbientLightDef {
+            color: Color::WHITE,
+            brightness: 0.0,
+        },
+        directional_light: DirectionalLightDef {
+            color: Color::WHITE,
+            illuminance: 0.0,
+            shadows_enabled: false,
+            rotation: Quat::IDENTITY,
+        },
+        clear_color: Color::BLACK,
+        difficulty: DifficultyCurve::default(),
+        flocking: None,
+        targets: vec![TargetVariant::default()],
+        powerups: Vec::new(),
+        indicators: TargetIndicators::default(),
+        camera_distance: CameraDistance::default(),
+        spawn_area: SpawnArea::default(),
+    }
+}
+
+fn stub_transition_start(_: On<StartTransition>, mut commands: Commands) {
+    commands.trigger(TransitionReachedMiddle);
+}
+
+fn stub_transition_continue(_: On<ContinueTransition>, mut commands: Commands) {
+    commands.trigger(TransitionEnded);
+}
+
+fn stub_load(mut commands: Commands, mut next_state: ResMut<NextState<AppState>>) {
+    commands.insert_resource(PermanentAssetHandles {
+        player: Handle::default(),
+        powerup_models: default(),
+        powerup_sounds: default(),
+        jump_sound: Handle::default(),
+        target_sound: Handle::default(),
+        laser_sound: Handle::default(),
+    });
+    commands.insert_resource(LevelAssetHandles {
+        environment: Handle::default(),
+        navmesh: Handle::default(),
+        targets: vec![(TargetVariant::default(), Handle::default())],
+        god: Handle::default(),
+        musics: Shuffle::new(&[Handle::default()]),
+    });
+
+    next_state.set(AppState::Setup);
+}
+
+/// Spawns a flat ground with a square navmesh on top
+fn stub_environment(mut commands: Commands, mut nav_meshes: ResMut<Assets<NavMesh3d>>) {
+    let s = STUB_LEVEL_HALF_SIZE;
+
+    // Counter clockwise, seen from above
+    let nav_mesh = NavigationMesh3d {
+        vertices: vec![
+            Vec3::new(-s, 0.0, -s),
+            Vec3::new(-s, 0.0, s),
+            Vec3::new(s, 0.0, s),
+            Vec3::new(s, 0.0, -s),
+        ],
+        polygons: vec![vec![0, 1, 2, 3]],
+        polygon_type_indices: vec![0],
+        height_mesh: None,
+    }
+    .validate()
+    .expect("The stub navmesh is valid");
+
+    let nav_mesh = nav_meshes.add(NavMesh3d {
+        nav_mesh: Arc::new(nav_mesh),
+        type_index_to_node_type: default(),
+    });
+
+    commands.spawn((
+        Name::new("Ground"),
+        DespawnOnExit(AppState::Playing),
+        Transform::IDENTITY,
+        RigidBody::Static,
+        Collider::half_space(Vec3::Y),
+        EnvironmentCollider,
+    ));
+
+    let archipelago = commands
+        .spawn((
+            Name::new("Navmesh archipelago"),
+            DespawnOnExit(AppState::Playing),
+            Archipelago3d::new(ArchipelagoOptions::from_agent_radius(1.0)),
+        ))
+        .id();
+
+    commands.spawn((
+        Name::new("NavMesh island"),
+        DespawnOnExit(AppState::Playing),
+        Transform::IDENTITY,
+        Island3dBundle {
+            island: Island,
+            archipelago_ref: ArchipelagoRef3d::new(archipelago),
+            nav_mesh: NavMeshHandle3d(nav_mesh),
+        },
+    ));
+}
+
+fn wait_for_navmesh(
+    navmesh: Single<&Archipelago3d>,
+    mut next_state: ResMut<NextState<SetupState>>,
+) {
+    if navmesh
+        .sample_point(
+            Vec3::ZERO,
+            &PointSampleDistance3d {
+                horizontal_distance: 5.0,
+                distance_above: 5.0,
+                distance_below: 5.0,
+                vertical_preference_ratio: 1.0,
+                animation_link_max_vertical_distance: 5.0,
+            },
+        )
+        .is_ok()
+    {
+        next_state.set(SetupState::Entities);
+    }
+}
+
+fn stub_animation(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Playing);
+}
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::{
+    game::{AppState, DespawnLaterPlease, PlayingState},
+    loader::{Fonts, LevelDef},
+    player::{Player, PlayerCamera},
+    target::Target,
+};
+
+/// Distance between the arrows and the edges of the screen, in pixels
+const SCREEN_MARGIN: f32 = 40.0;
+const ARROW_SIZE: f32 = 48.0;
+
+pub struct IndicatorPlugin;
+
+impl Plugin for IndicatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            PostUpdate,
+            update_indicators
+                .after(TransformSystems::Propagate)
+                .run_if(in_state(PlayingState::Playing)),
+        )
+        .add_systems(OnExit(PlayingState::Playing), hide_indicators);
+    }
+}
+
+/// Arrows on the edges of the screen pointing at the targets out of view
+///
+/// Set per level with `indicators` in the `.level.ron`
+#[derive(Reflect, Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct TargetIndicators {
+    /// Only point at that many targets, the nearest ones, all of them when `None`
+    pub nearest: Option<usize>,
+    /// Only show the arrows once that few targets remain, always when `None`
+    pub remaining: Option<usize>,
+}
+
+impl Default for TargetIndicators {
+    fn default() -> Self {
+        Self {
+            nearest: Some(3),
+            remaining: Some(5),
+        }
+    }
+}
+
+#[derive(Component)]
+struct IndicatorArrow;
+
+fn update_indicators(
+    mut commands: Commands,
+    camera: Single<(&Camera, &GlobalTransform), With<PlayerCamera>>,
+    player: Single<&GlobalTransform, With<Player>>,
+    targets: Query<&GlobalTransform, (With<Target>, Without<DespawnLaterPlease>)>,
+    mut arrows: Query<(&mut Node, &mut UiTransform, &mut Visibility), With<IndicatorArrow>>,
+    level_def: Res<LevelDef>,
+    fonts: Res<Fonts>,
+) {
+    let (camera, camera_transform) = camera.into_inner();
+    let Some(size) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    let settings = level_def.indicators;
+    let mut remaining: Vec<Vec3> = targets.iter().map(GlobalTransform::translation).collect();
+
+    if settings.remaining.is_some_and(|max| remaining.len() > max) {
+        remaining.clear();
+    }
+
+    if let Some(nearest) = settings.nearest {
+        let from = player.translation();
+        remaining.sort_by(|a, b| {
+            a.distance_squared(from)
+                .total_cmp(&b.distance_squared(from))
+        });
+        remaining.truncate(nearest);
+    }
+
+    let center = size * 0.5;
+    let half = center - SCREEN_MARGIN;
+    let to_view = camera_transform.affine().inverse();
+
+    // Screen position and angle of the arrow for each target out of view
+    let placements: Vec<(Vec2, f32)> = remaining
+        .into_iter()
+        .filter(|position| {
+            // Targets behind the camera have no viewport position
+            camera
+                .world_to_viewport(camera_transform, *position)
+                .ok()
+                .is_none_or(|point| !Rect::from_corners(Vec2::ZERO, size).contains(point))
+        })
+        .filter_map(|position| {
+            // Sideways and up in the camera space, also right for targets behind the camera
+            let local = to_view.transform_point3(position);
+            let direction = Vec2::new(local.x, -local.y).try_normalize()?;
+
+            // Stretch to the screen border
+            let scale = (half.x / direction.x.abs()).min(half.y / direction.y.abs());
+            Some((center + direction * scale, direction.to_angle()))
+        })
+        .collect();
+
+    let mut arrows = arrows.iter_mut();
+    for (point, angle) in placements {
+        let node = Node {
+            position_type: PositionType::Absolute,
+            left: px(point.x - ARROW_SIZE * 0.5),
+            top: px(point.y - ARROW_SIZE * 0.5),
+            width: px(ARROW_SIZE),
+            height: px(ARROW_SIZE),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..Default::default()
+        };
+        let transform = UiTransform::from_rotation(Rot2::radians(angle));
+
+        match arrows.next() {
+            Some((mut arrow_node, mut arrow_transform, mut visibility)) => {
+                *arrow_node = node;
+                *arrow_transform = transform;
+                *visibility = Visibility::Inherited;
+            }
+            None => {
+                commands.spawn((
+                    IndicatorArrow,
+                    GlobalZIndex(-1),
+                    DespawnOnExit(AppState::Playing),
+                    node,
+                    transform,
+                    Text::new(">"),
+                    TextFont {
+                        font: fonts.blue_winter.clone(),
+                        font_size: ARROW_SIZE,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.95, 0.75, 0.1)),
+                    TextShadow::default(),
+                ));
+            }
+        }
+    }
+
+    // Left over from when more targets were out of view
+    for (_, _, mut visibility) in arrows {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn hide_indicators(mut arrows: Query<&mut Visibility, With<IndicatorArrow>>) {
+    for mut visibility in &mut arrows {
+        *visibility = Visibility::Hidden;
+    }
+}
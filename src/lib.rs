@@ -0,0 +1,31 @@
+pub mod anim;
+pub mod audio;
+pub mod bindings;
+pub mod bot;
+pub mod combo;
+pub mod difficulty;
+pub mod effect;
+pub mod env;
+pub mod flee;
+pub mod flocking;
+pub mod game;
+pub mod god;
+pub mod headless;
+pub mod indicator;
+pub mod loader;
+pub mod menus;
+pub mod mice;
+pub mod minimap;
+pub mod physics;
+pub mod player;
+pub mod powerup;
+pub mod replay;
+pub mod scores;
+pub mod seed;
+pub mod shuffle;
+pub mod skeleton;
+pub mod storage;
+pub mod target;
+pub mod transition;
+pub mod wander;
+pub mod watchdog;
@@ -1,76 +1,282 @@
-use bevy::prelude::*;
+use bevy::{
+    asset::{AssetLoader, LoadContext, LoadDirectError, io::Reader},
+    platform::collections::HashMap,
+    prelude::*,
+};
 use bevy_rerecast::Navmesh;
 use bevy_seedling::sample::AudioSample;
+use serde::Deserialize;
+use thiserror::Error;
 
 use crate::{
+    difficulty::DifficultyCurve,
+    flocking::Flocking,
     game::{AppState, LoadingState},
     god::GodBehavior,
+    indicator::TargetIndicators,
+    player::CameraDistance,
+    powerup::{PowerupChance, PowerupKind},
     shuffle::Shuffle,
-    target::TargetBehavior,
+    target::{SpawnArea, TargetBehaviors, TargetVariant},
 };
 
 pub struct LoaderPlugin;
 
 impl Plugin for LoaderPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<PreLoadAssets>()
+        app.init_asset::<LevelDef>()
+            .init_asset::<LevelList>()
+            // Registered once every behavior plugin was added, see `finish`
+            .preregister_asset_loader::<LevelDefLoader>(LevelDefLoader::EXTENSIONS)
+            .init_asset_loader::<LevelListLoader>()
+            .register_type::<PreLoadAssets>()
             .init_resource::<PreLoadAssets>()
-            .insert_resource(Shuffle::new(&[LevelDef::MICE]))
-            .add_systems(OnEnter(LoadingState::Loading), load_assets)
-            .add_systems(Update, check_load.run_if(in_state(LoadingState::Loading)))
+            .add_systems(
+                Update,
+                (
+                    update_level_shuffle,
+                    load_assets.run_if(
+                        in_state(LoadingState::Loading)
+                            .and(resource_exists::<Shuffle<LevelDef>>)
+                            .and(not(resource_exists::<LevelAssetHandles>)),
+                    ),
+                    check_load.run_if(
+                        in_state(LoadingState::Loading).and(resource_exists::<LevelAssetHandles>),
+                    ),
+                )
+                    .chain(),
+            )
             .add_systems(OnExit(AppState::Playing), unload_assets);
     }
+
+    fn finish(&self, app: &mut App) {
+        let behaviors = app
+            .world()
+            .get_resource::<TargetBehaviors>()
+            .map(TargetBehaviors::names)
+            .unwrap_or_default();
+
+        app.register_asset_loader(LevelDefLoader { behaviors });
+    }
 }
 
-#[derive(Resource, Clone)]
+/// Definition of a level, loaded from `levels/<prefix>/<prefix>.level.ron`
+#[derive(Asset, Resource, TypePath, Clone, Deserialize)]
 pub struct LevelDef {
-    pub prefix: &'static str,
-    pub goal: &'static str,
-    pub target_behavior: TargetBehavior,
+    /// Name of the level folder, filled by the loader
+    #[serde(skip)]
+    pub prefix: String,
+    /// Folder to take the models, navmesh and musics from, defaults to the level own folder
+    #[serde(default)]
+    pub assets: Option<String>,
+    pub goal: String,
+    /// Name of a registered [`crate::target::TargetBehavior`]
+    pub target_behavior: String,
     pub god_behavior: GodBehavior,
-    pub musics: [&'static str; 3],
-    pub ambient_light: GlobalAmbientLight,
-    pub directional_light: DirectionalLight,
-    pub directional_light_transform: Transform,
+    pub musics: Vec<String>,
+    pub ambient_light: AmbientLightDef,
+    pub directional_light: DirectionalLightDef,
     pub clear_color: Color,
+    /// Falls back to [`DifficultyCurve::default`] when missing
+    #[serde(default)]
+    pub difficulty: DifficultyCurve,
+    /// Targets move on their own when missing
+    #[serde(default)]
+    pub flocking: Option<Flocking>,
+    /// Kinds of targets to pick from, a single mouse worth 100 when missing
+    #[serde(default = "default_targets")]
+    pub targets: Vec<TargetVariant>,
+    /// Kinds of powerups to pick from, only speed boosts when missing
+    #[serde(default = "default_powerups")]
+    pub powerups: Vec<PowerupChance>,
+    #[serde(default)]
+    pub indicators: TargetIndicators,
+    #[serde(default)]
+    pub camera_distance: CameraDistance,
+    /// Where targets spawn, and respawn once stuck
+    #[serde(default)]
+    pub spawn_area: SpawnArea,
+}
+
+fn default_targets() -> Vec<TargetVariant> {
+    vec![TargetVariant::default()]
+}
+
+fn default_powerups() -> Vec<PowerupChance> {
+    vec![PowerupChance {
+        kind: PowerupKind::Speed,
+        weight: 1,
+    }]
 }
 
 impl LevelDef {
-    const MICE: LevelDef = LevelDef {
-        prefix: "mice",
-        goal: "Mice for the Cat-God",
-        target_behavior: TargetBehavior::Mice,
-        god_behavior: GodBehavior::Cat,
-        musics: [
-            "apple_cider-zane_little_music.ogg",
-            "nature_sketch-remaxim.ogg",
-            "the_secret_within_the_silent_woods-hitctrl.ogg",
-        ],
-        ambient_light: GlobalAmbientLight {
-            color: Color::WHITE,
-            brightness: 1200.0,
+    pub fn assets_prefix(&self) -> &str {
+        self.assets.as_deref().unwrap_or(&self.prefix)
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct AmbientLightDef {
+    pub color: Color,
+    pub brightness: f32,
+}
+
+impl From<&AmbientLightDef> for GlobalAmbientLight {
+    fn from(def: &AmbientLightDef) -> Self {
+        GlobalAmbientLight {
+            color: def.color,
+            brightness: def.brightness,
             affects_lightmapped_meshes: true,
-        },
-        directional_light: DirectionalLight {
-            color: Color::WHITE,
-            illuminance: light_consts::lux::AMBIENT_DAYLIGHT,
-            shadows_enabled: true,
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+pub struct DirectionalLightDef {
+    pub color: Color,
+    pub illuminance: f32,
+    pub shadows_enabled: bool,
+    pub rotation: Quat,
+}
+
+impl DirectionalLightDef {
+    pub fn light(&self) -> DirectionalLight {
+        DirectionalLight {
+            color: self.color,
+            illuminance: self.illuminance,
+            shadows_enabled: self.shadows_enabled,
             shadow_depth_bias: DirectionalLight::DEFAULT_SHADOW_DEPTH_BIAS,
             shadow_normal_bias: DirectionalLight::DEFAULT_SHADOW_NORMAL_BIAS,
             affects_lightmapped_mesh_diffuse: true,
-        },
-        directional_light_transform: Transform::from_rotation(Quat::from_xyzw(
-            -0.5257311, -0.0, -0.0, 0.85065085,
-        )),
-        clear_color: Color::hsl(191.0, 0.83, 0.93),
-    };
+        }
+    }
+
+    pub fn transform(&self) -> Transform {
+        Transform::from_rotation(self.rotation)
+    }
+}
+
+/// All the levels listed in `levels/index.levels.ron`
+///
+/// The levels are not found by scanning `levels/`: the web build fetches assets one by one
+/// over HTTP and can't list folders, so levels are declared by their folder name in the index
+#[derive(Asset, TypePath)]
+pub struct LevelList(pub Vec<LevelDef>);
+
+#[derive(Debug, Error)]
+pub enum LevelLoaderError {
+    #[error("could not read level file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed level file: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("level file must be inside a level folder: {0}")]
+    MissingPrefix(String),
+    #[error("level has no target variant with a weight above 0")]
+    NoTargetWeight,
+    #[error("unknown target behavior {name}, expected one of {known:?}")]
+    UnknownBehavior {
+        name: String,
+        known: Vec<&'static str>,
+    },
+    #[error(transparent)]
+    Level(#[from] Box<LoadDirectError>),
+}
+
+#[derive(TypePath)]
+struct LevelDefLoader {
+    /// Names of the registered target behaviors, a level can only use one of those
+    behaviors: Vec<&'static str>,
+}
+
+impl LevelDefLoader {
+    const EXTENSIONS: &[&str] = &["level.ron"];
+}
+
+impl AssetLoader for LevelDefLoader {
+    type Asset = LevelDef;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let mut level_def = ron::de::from_bytes::<LevelDef>(&bytes)?;
 
-    // const SKELETON: LevelDef = LevelDef {
-    //     prefix: "skel",
-    //     goal: "Bones for the Necromancer-God",
-    //     target_behavior: TargetBehavior::Skeleton,
-    //     god_behavior: GodBehavior::Necromencer,
-    // };
+        level_def.prefix = load_context
+            .path()
+            .path()
+            .parent()
+            .and_then(|folder| folder.file_name())
+            .and_then(|folder| folder.to_str())
+            .ok_or_else(|| LevelLoaderError::MissingPrefix(load_context.path().to_string()))?
+            .to_string();
+
+        if !self
+            .behaviors
+            .iter()
+            .any(|name| *name == level_def.target_behavior)
+        {
+            return Err(LevelLoaderError::UnknownBehavior {
+                name: level_def.target_behavior,
+                known: self.behaviors.clone(),
+            });
+        }
+
+        if level_def.targets.iter().all(|variant| variant.weight == 0) {
+            return Err(LevelLoaderError::NoTargetWeight);
+        }
+
+        Ok(level_def)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        Self::EXTENSIONS
+    }
+}
+
+#[derive(Default, TypePath)]
+struct LevelListLoader;
+
+impl AssetLoader for LevelListLoader {
+    type Asset = LevelList;
+    type Settings = ();
+    type Error = LevelLoaderError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let prefixes = ron::de::from_bytes::<Vec<String>>(&bytes)?;
+
+        let mut levels = Vec::with_capacity(prefixes.len());
+        for prefix in prefixes {
+            let level = load_context
+                .loader()
+                .immediate()
+                .load::<LevelDef>(format!("levels/{prefix}/{prefix}.level.ron"))
+                .await
+                .map_err(Box::new)?;
+
+            levels.push(level.take());
+        }
+
+        Ok(LevelList(levels))
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["levels.ron"]
+    }
 }
 
 #[derive(Resource)]
@@ -88,6 +294,7 @@ impl FromWorld for Fonts {
 
 #[derive(Reflect, Resource)]
 pub struct PreLoadAssets {
+    pub levels: Handle<LevelList>,
     pub eye: Handle<Scene>,
     pub eye_animation_graph: Handle<AnimationGraph>,
     pub eye_close: AnimationNodeIndex,
@@ -101,6 +308,7 @@ impl FromWorld for PreLoadAssets {
     fn from_world(world: &mut World) -> Self {
         let asset_server = world.get_resource::<AssetServer>().unwrap();
 
+        let levels = asset_server.load("levels/index.levels.ron");
         let eye = asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/eye.glb"));
         let day_bg = asset_server.load("textures/day.png");
         let night_bg = asset_server.load("textures/night.png");
@@ -117,6 +325,7 @@ impl FromWorld for PreLoadAssets {
             .add(graph);
 
         Self {
+            levels,
             eye,
             eye_animation_graph,
             eye_close: indices[0],
@@ -131,9 +340,9 @@ impl FromWorld for PreLoadAssets {
 #[derive(Resource)]
 pub struct PermanentAssetHandles {
     pub player: Handle<Scene>,
-    pub cheese: Handle<Scene>,
+    pub powerup_models: HashMap<PowerupKind, Handle<Scene>>,
+    pub powerup_sounds: HashMap<PowerupKind, Handle<AudioSample>>,
     pub jump_sound: Handle<AudioSample>,
-    pub powerup_sound: Handle<AudioSample>,
     pub target_sound: Handle<AudioSample>,
     pub laser_sound: Handle<AudioSample>,
 }
@@ -142,14 +351,20 @@ impl PermanentAssetHandles {
     fn are_loaded(&self, asset_server: &AssetServer) -> bool {
         let handles = [
             self.player.clone().untyped(),
-            self.cheese.clone().untyped(),
             self.jump_sound.clone().untyped(),
-            self.powerup_sound.clone().untyped(),
             self.target_sound.clone().untyped(),
             self.laser_sound.clone().untyped(),
         ];
 
         handles.iter().all(|h| asset_server.is_loaded(h.id()))
+            && self
+                .powerup_models
+                .values()
+                .all(|h| asset_server.is_loaded(h.id()))
+            && self
+                .powerup_sounds
+                .values()
+                .all(|h| asset_server.is_loaded(h.id()))
     }
 }
 
@@ -157,7 +372,8 @@ impl PermanentAssetHandles {
 pub struct LevelAssetHandles {
     pub environment: Handle<Gltf>,
     pub navmesh: Handle<Navmesh>,
-    pub target: Handle<Scene>,
+    /// Every variant of the level, with its model
+    pub targets: Vec<(TargetVariant, Handle<Scene>)>,
     pub god: Handle<Scene>,
     pub musics: Shuffle<Handle<AudioSample>>,
 }
@@ -167,10 +383,38 @@ impl LevelAssetHandles {
         let handles = [
             self.environment.clone().untyped(),
             self.navmesh.clone().untyped(),
-            self.target.clone().untyped(),
         ];
 
         handles.iter().all(|h| asset_server.is_loaded(h.id()))
+            && self
+                .targets
+                .iter()
+                .all(|(_, h)| asset_server.is_loaded(h.id()))
+    }
+}
+
+/// (Re)builds the level shuffle once the level list is loaded, or when it is hot-reloaded
+fn update_level_shuffle(
+    mut commands: Commands,
+    mut events: MessageReader<AssetEvent<LevelList>>,
+    level_lists: Res<Assets<LevelList>>,
+) {
+    for event in events.read() {
+        let (AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id }) = event
+        else {
+            continue;
+        };
+
+        let Some(level_list) = level_lists.get(*id) else {
+            continue;
+        };
+
+        if level_list.0.is_empty() {
+            error!("No levels are listed in levels/index.levels.ron");
+            continue;
+        }
+
+        commands.insert_resource(Shuffle::new(&level_list.0));
     }
 }
 
@@ -185,9 +429,18 @@ fn load_assets(
         // info!("Loading permanent assets");
         commands.insert_resource(PermanentAssetHandles {
             player: asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/player.glb")),
-            cheese: asset_server.load(GltfAssetLabel::Scene(0).from_asset("models/cheese.glb")),
+            powerup_models: PowerupKind::ALL
+                .into_iter()
+                .map(|kind| {
+                    let model = GltfAssetLabel::Scene(0).from_asset(kind.model());
+                    (kind, asset_server.load(model))
+                })
+                .collect(),
+            powerup_sounds: PowerupKind::ALL
+                .into_iter()
+                .map(|kind| (kind, asset_server.load(kind.sound())))
+                .collect(),
             jump_sound: asset_server.load("sfx/jump.wav"),
-            powerup_sound: asset_server.load("sfx/powerup.wav"),
             target_sound: asset_server.load("sfx/target.wav"),
             laser_sound: asset_server.load("sfx/laser.wav"),
         });
@@ -199,25 +452,40 @@ fn load_assets(
     let level_def = level_shuffle.next(&mut rng);
 
     commands.insert_resource(level_def.clone());
+    commands.insert_resource(level_def.difficulty.clone());
+    match level_def.flocking {
+        Some(flocking) => commands.insert_resource(flocking),
+        None => commands.remove_resource::<Flocking>(),
+    }
 
     // info!("Loading level");
-    let env_path = format!("levels/{}/environment.glb", level_def.prefix);
-    let nav_path = format!("levels/{}/navmesh.nav", level_def.prefix);
-    let tar_path = format!("levels/{}/target.glb", level_def.prefix);
-    let god_path = format!("levels/{}/god.glb", level_def.prefix);
+    let prefix = level_def.assets_prefix();
+    let env_path = format!("levels/{prefix}/environment.glb");
+    let nav_path = format!("levels/{prefix}/navmesh.nav");
+    let god_path = format!("levels/{prefix}/god.glb");
 
     let musics = level_def
         .musics
         .iter()
-        .map(|music| {
-            asset_server.load::<AudioSample>(format!("music/{}/{}", level_def.prefix, music))
-        })
+        .map(|music| asset_server.load::<AudioSample>(format!("music/{prefix}/{music}")))
         .collect::<Vec<_>>();
 
+    let targets = level_def
+        .targets
+        .iter()
+        .map(|variant| {
+            let path = format!("levels/{prefix}/{}", variant.model);
+            (
+                variant.clone(),
+                asset_server.load(GltfAssetLabel::Scene(0).from_asset(path)),
+            )
+        })
+        .collect();
+
     commands.insert_resource(LevelAssetHandles {
         environment: asset_server.load(env_path),
         navmesh: asset_server.load(nav_path),
-        target: asset_server.load(GltfAssetLabel::Scene(0).from_asset(tar_path)),
+        targets,
         god: asset_server.load(GltfAssetLabel::Scene(0).from_asset(god_path)),
         musics: Shuffle::new(&musics),
     });
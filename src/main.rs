@@ -1,18 +1,5 @@
-pub mod anim;
-pub mod audio;
-pub mod env;
-pub mod game;
-pub mod god;
-pub mod loader;
-pub mod menus;
-pub mod physics;
-pub mod player;
-pub mod powerup;
-pub mod shuffle;
-pub mod target;
-pub mod transition;
-
 use bevy::{asset::AssetMetaCheck, prelude::*};
+use jam7::*;
 
 fn main() {
     let mut app = App::new();
@@ -23,6 +10,9 @@ fn main() {
         }),
         bevy_framepace::FramepacePlugin,
         bevy_rand::prelude::EntropyPlugin::<bevy_prng::ChaCha20Rng>::default(),
+        seed::SeedPlugin,
+    ))
+    .add_plugins((
         audio::AudioPlugin,
         loader::LoaderPlugin,
         transition::TransitionPlugin,
@@ -33,9 +23,24 @@ fn main() {
         powerup::PowerupPlugin,
         game::GamePlugin,
         menus::MenusPlugin,
+        scores::ScoresPlugin,
+        replay::ReplayPlugin,
         anim::AnimPlugin,
+        mice::MicePlugin,
+        skeleton::SkeletonPlugin,
     ));
 
+    // Play back a recorded run: `--replay <file>`
+    #[cfg(not(feature = "web"))]
+    if let Some(path) = std::env::args().skip_while(|arg| arg != "--replay").nth(1) {
+        match replay::Replay::read(&path) {
+            Ok(replay) => {
+                app.insert_resource(replay::ReplayPlayback::new(replay));
+            }
+            Err(err) => error!("Could not load replay {path}: {err}"),
+        }
+    }
+
     #[cfg(feature = "dev")]
     app.add_plugins((
         bevy::remote::RemotePlugin::default(),
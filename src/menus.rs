@@ -1,13 +1,21 @@
-use bevy::{input_focus::InputFocus, prelude::*};
+use bevy::{
+    input::keyboard::{Key, KeyboardInput},
+    input_focus::InputFocus,
+    prelude::*,
+};
 #[cfg(feature = "dev")]
 use bevy_inspector_egui::bevy_egui::{EguiContext, PrimaryEguiContext};
 
 use crate::{
+    bindings::{BindingSlot, Capture},
     game::{AppState, GameSettings, GameState, LoadingState, MenuState, PlayingState},
     loader::{Fonts, PreLoadAssets},
+    scores::{HighScore, HighScores, NewHighScore},
+    seed::{AmbientRng, RunSeed},
 };
 
 const MENUS_BG_COLOR: BackgroundColor = BackgroundColor(Color::linear_rgba(0.05, 0.05, 0.08, 0.8));
+const NEW_HIGH_SCORE_COLOR: Color = Color::linear_rgba(0.9, 0.6, 0.1, 0.5);
 
 pub struct MenusPlugin;
 
@@ -15,8 +23,11 @@ impl Plugin for MenusPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Fonts>()
             .init_resource::<InputFocus>()
+            .init_resource::<SeedInput>()
+            .init_resource::<Rebinding>()
             .add_systems(Startup, setup)
             .add_systems(OnEnter(MenuState::Main), setup_main_menu)
+            .add_systems(OnExit(MenuState::Main), stop_seed_input)
             .add_systems(OnEnter(MenuState::Settings), cleanup::<MainMenuTag>)
             .add_systems(OnEnter(LoadingState::Loading), cleanup::<MainMenuTag>)
             .add_systems(OnEnter(MenuState::Settings), setup_settings_menu)
@@ -29,10 +40,38 @@ impl Plugin for MenusPlugin {
                 OnExit(PlayingState::SettingsMenu),
                 cleanup::<SettingsMenuTag>,
             )
+            .add_systems(OnEnter(MenuState::Controls), setup_controls_menu)
+            .add_systems(
+                OnExit(MenuState::Controls),
+                (cleanup::<ControlsMenuTag>, stop_rebinding),
+            )
+            .add_systems(
+                OnEnter(PlayingState::ControlsMenu),
+                setup_playing_controls_menu,
+            )
+            .add_systems(
+                OnExit(PlayingState::ControlsMenu),
+                (cleanup::<ControlsMenuTag>, stop_rebinding),
+            )
             .add_systems(OnEnter(PlayingState::Paused), setup_pause_menu)
             .add_systems(OnEnter(AppState::ScoreMenu), setup_score_menu)
-            .add_systems(Update, button_system)
+            .add_systems(
+                Update,
+                (
+                    button_system,
+                    (
+                        (type_seed, update_seed_text).chain(),
+                        update_difficulty_mode_text,
+                    )
+                        .run_if(in_state(MenuState::Main)),
+                    (capture_binding, update_binding_texts).chain().run_if(
+                        in_state(MenuState::Controls).or(in_state(PlayingState::ControlsMenu)),
+                    ),
+                ),
+            )
             .add_observer(on_quit_click)
+            .add_observer(on_random_seed_click)
+            .add_observer(on_next_difficulty_mode_click)
             .add_observer(on_settings_changed);
     }
 }
@@ -82,7 +121,7 @@ fn setup_main_menu(mut commands: Commands, fonts: Res<Fonts>, handles: Res<PreLo
             children![(
                 Node {
                     width: percent(50),
-                    height: percent(60),
+                    height: percent(80),
                     flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
@@ -93,6 +132,17 @@ fn setup_main_menu(mut commands: Commands, fonts: Res<Fonts>, handles: Res<PreLo
                 children![
                     title("Dreams for the Fever Gods", fonts.blue_winter.clone()),
                     button("Sleep", fonts.blue_winter.clone(), 300, 75, UiEvents::Play),
+                    seed_ui(&fonts),
+                    (
+                        DifficultyModeButton,
+                        button(
+                            "",
+                            fonts.blue_winter.clone(),
+                            250,
+                            63,
+                            UiEvents::NextDifficultyMode
+                        ),
+                    ),
                     button(
                         "Settings",
                         fonts.blue_winter.clone(),
@@ -112,6 +162,17 @@ fn setup_main_menu(mut commands: Commands, fonts: Res<Fonts>, handles: Res<PreLo
                 children![
                     title("Dreams for the Fever Gods", fonts.blue_winter.clone()),
                     button("Sleep", fonts.blue_winter.clone(), 300, 75, UiEvents::Play),
+                    seed_ui(&fonts),
+                    (
+                        DifficultyModeButton,
+                        button(
+                            "",
+                            fonts.blue_winter.clone(),
+                            250,
+                            63,
+                            UiEvents::NextDifficultyMode
+                        ),
+                    ),
                     button(
                         "Settings",
                         fonts.blue_winter.clone(),
@@ -125,6 +186,129 @@ fn setup_main_menu(mut commands: Commands, fonts: Res<Fonts>, handles: Res<PreLo
     ));
 }
 
+#[derive(Component)]
+struct SeedButton;
+
+/// Seed being typed in the main menu, `None` when not editing
+#[derive(Resource, Default)]
+struct SeedInput(Option<String>);
+
+fn seed_ui(fonts: &Fonts) -> impl Bundle {
+    (
+        Node {
+            column_gap: px(24),
+            align_items: AlignItems::Center,
+            ..Default::default()
+        },
+        children![
+            text("Seed", fonts.blue_winter.clone(), 32.0),
+            (
+                SeedButton,
+                button("", fonts.blue_winter.clone(), 200, 50, UiEvents::EditSeed),
+            ),
+            button(
+                "Random",
+                fonts.blue_winter.clone(),
+                150,
+                50,
+                UiEvents::RandomSeed
+            ),
+        ],
+    )
+}
+
+fn type_seed(
+    mut keyboard: MessageReader<KeyboardInput>,
+    mut input: ResMut<SeedInput>,
+    mut seed: ResMut<RunSeed>,
+) {
+    let Some(buffer) = &mut input.0 else {
+        keyboard.clear();
+        return;
+    };
+
+    let mut done = false;
+
+    for event in keyboard.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+
+        match &event.logical_key {
+            Key::Character(chars) => {
+                for c in chars.chars().filter(char::is_ascii_hexdigit) {
+                    if buffer.len() < 8 {
+                        buffer.push(c.to_ascii_uppercase());
+                    }
+                }
+            }
+            Key::Backspace => {
+                buffer.pop();
+            }
+            Key::Enter | Key::Escape => done = true,
+            _ => {}
+        }
+    }
+
+    // The seed is applied as it is typed, so starting a run while editing doesn't lose it
+    if let Ok(new_seed) = buffer.parse::<RunSeed>()
+        && *seed != new_seed
+    {
+        *seed = new_seed;
+    }
+
+    if done {
+        input.0 = None;
+    }
+}
+
+fn update_seed_text(
+    seed: Res<RunSeed>,
+    input: Res<SeedInput>,
+    buttons: Query<&Children, With<SeedButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    let new = match &input.0 {
+        Some(buffer) => format!("{buffer}_"),
+        None => seed.to_string(),
+    };
+
+    for children in &buttons {
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child)
+                && text.0 != new
+            {
+                text.0.clone_from(&new);
+            }
+        }
+    }
+}
+
+fn stop_seed_input(mut input: ResMut<SeedInput>) {
+    input.0 = None;
+}
+
+#[derive(Component)]
+struct DifficultyModeButton;
+
+fn update_difficulty_mode_text(
+    settings: Res<GameSettings>,
+    buttons: Query<&Children, With<DifficultyModeButton>>,
+    mut texts: Query<&mut Text>,
+) {
+    let new = settings.difficulty_mode.name();
+
+    for children in &buttons {
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child)
+                && text.0 != new
+            {
+                text.0 = new.to_string();
+            }
+        }
+    }
+}
+
 #[derive(Component)]
 struct SettingsMenuTag;
 
@@ -148,7 +332,12 @@ fn setup_settings_menu(
                 image: handles.day_bg.clone(),
                 ..Default::default()
             },
-            children![settings_ui(&fonts, &settings, UiEvents::MainMenuLocal)],
+            children![settings_ui(
+                &fonts,
+                &settings,
+                UiEvents::Controls,
+                UiEvents::MainMenuLocal
+            )],
         ),
     ));
 }
@@ -169,13 +358,23 @@ fn setup_playing_settings_menu(
                 ..Default::default()
             },
             MENUS_BG_COLOR,
-            children![settings_ui(&fonts, &settings, UiEvents::Pause)],
+            children![settings_ui(
+                &fonts,
+                &settings,
+                UiEvents::PlayingControls,
+                UiEvents::Pause
+            )],
         ),
     ));
 }
 
 #[allow(clippy::too_many_lines)]
-fn settings_ui(fonts: &Fonts, settings: &GameSettings, back: UiEvents) -> impl Bundle {
+fn settings_ui(
+    fonts: &Fonts,
+    settings: &GameSettings,
+    controls: UiEvents,
+    back: UiEvents,
+) -> impl Bundle {
     (
         Node {
             width: percent(60),
@@ -196,7 +395,7 @@ fn settings_ui(fonts: &Fonts, settings: &GameSettings, back: UiEvents) -> impl B
                         RepeatedGridTrack::percent(1, 50.0),
                         RepeatedGridTrack::percent(3, 16.66),
                     ],
-                    grid_template_rows: RepeatedGridTrack::px(4, 50.0),
+                    grid_template_rows: RepeatedGridTrack::px(5, 50.0),
                     row_gap: px(36),
                     column_gap: px(24),
                     align_items: AlignItems::Center,
@@ -246,6 +445,27 @@ fn settings_ui(fonts: &Fonts, settings: &GameSettings, back: UiEvents) -> impl B
                         UiEvents::SettingsChange(settings.cam_y(0.1)),
                     ),
                     // -----------------------------------------------------------------
+                    text("Camera distance", fonts.blue_winter.clone(), 32.0),
+                    button(
+                        "<",
+                        fonts.blue_winter.clone(),
+                        50,
+                        50,
+                        UiEvents::SettingsChange(settings.zoom(-1.0)),
+                    ),
+                    text(
+                        format!("{:^5.0}", settings.camera_distance),
+                        fonts.blue_winter.clone(),
+                        32.0,
+                    ),
+                    button(
+                        ">",
+                        fonts.blue_winter.clone(),
+                        50,
+                        50,
+                        UiEvents::SettingsChange(settings.zoom(1.0)),
+                    ),
+                    // -----------------------------------------------------------------
                     text("Music volume", fonts.blue_winter.clone(), 32.0),
                     button(
                         "<",
@@ -290,11 +510,240 @@ fn settings_ui(fonts: &Fonts, settings: &GameSettings, back: UiEvents) -> impl B
                 ],
             ),
             padding(UiRect::bottom(px(50))),
+            button("Controls", fonts.blue_winter.clone(), 250, 50, controls),
             button("Back", fonts.blue_winter.clone(), 200, 50, back),
         ],
     )
 }
 
+#[derive(Component)]
+struct ControlsMenuTag;
+
+/// Slot waiting for a key or button in the controls menu, `None` when not rebinding
+#[derive(Resource, Default)]
+struct Rebinding {
+    slot: Option<BindingSlot>,
+    /// Tells which action got the previous key after a swap
+    notice: Option<String>,
+}
+
+#[derive(Component)]
+struct BindingButton(BindingSlot);
+
+#[derive(Component)]
+struct RebindNotice;
+
+fn setup_controls_menu(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    settings: Res<GameSettings>,
+    handles: Res<PreLoadAssets>,
+) {
+    commands.spawn((
+        ControlsMenuTag,
+        (
+            Node {
+                width: percent(100),
+                height: percent(100),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            ImageNode {
+                image: handles.day_bg.clone(),
+                ..Default::default()
+            },
+            children![controls_ui(&fonts, &settings, UiEvents::Settings)],
+        ),
+    ));
+}
+
+fn setup_playing_controls_menu(
+    mut commands: Commands,
+    fonts: Res<Fonts>,
+    settings: Res<GameSettings>,
+) {
+    commands.spawn((
+        ControlsMenuTag,
+        (
+            Node {
+                width: percent(100),
+                height: percent(100),
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                ..Default::default()
+            },
+            MENUS_BG_COLOR,
+            children![controls_ui(&fonts, &settings, UiEvents::PlayingSettings)],
+        ),
+    ));
+}
+
+fn controls_ui(fonts: &Fonts, settings: &GameSettings, back: UiEvents) -> impl Bundle {
+    let rows = BindingSlot::ALL
+        .into_iter()
+        .map(|slot| {
+            (
+                Node {
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::fr(2, 1.0),
+                    align_items: AlignItems::Center,
+                    justify_items: JustifyItems::Center,
+                    ..Default::default()
+                },
+                children![
+                    text(slot.label(), fonts.blue_winter.clone(), 28.0),
+                    (
+                        BindingButton(slot),
+                        button(
+                            settings.bindings.name(slot),
+                            fonts.blue_winter.clone(),
+                            250,
+                            45,
+                            UiEvents::Rebind(slot),
+                        ),
+                    ),
+                ],
+            )
+        })
+        .collect::<Vec<_>>();
+
+    (
+        Node {
+            width: percent(60),
+            height: percent(90),
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::SpaceBetween,
+            ..Default::default()
+        },
+        children![
+            title("Controls", fonts.blue_winter.clone()),
+            (
+                Node {
+                    width: percent(100),
+                    flex_direction: FlexDirection::Column,
+                    row_gap: px(8),
+                    ..Default::default()
+                },
+                Children::spawn(SpawnIter(rows.into_iter())),
+            ),
+            (
+                Node {
+                    width: percent(100),
+                    display: Display::Grid,
+                    grid_template_columns: RepeatedGridTrack::fr(2, 1.0),
+                    align_items: AlignItems::Center,
+                    justify_items: JustifyItems::Center,
+                    ..Default::default()
+                },
+                children![
+                    text("Swap sticks", fonts.blue_winter.clone(), 28.0),
+                    button(
+                        if settings.bindings.swap_sticks {
+                            "On"
+                        } else {
+                            "Off"
+                        },
+                        fonts.blue_winter.clone(),
+                        250,
+                        45,
+                        UiEvents::SettingsChange(settings.swap_sticks()),
+                    ),
+                ],
+            ),
+            (RebindNotice, text("", fonts.blue_winter.clone(), 24.0)),
+            button(
+                "Defaults",
+                fonts.blue_winter.clone(),
+                250,
+                50,
+                UiEvents::SettingsChange(settings.default_bindings()),
+            ),
+            button("Back", fonts.blue_winter.clone(), 200, 50, back),
+        ],
+    )
+}
+
+/// Bind the next key or gamepad button pressed to the slot waiting for one
+///
+/// A gamepad button cancels for the keyboard slots, and a key for the gamepad ones,
+/// clicking the slot again cancels too
+fn capture_binding(
+    mut rebinding: ResMut<Rebinding>,
+    mut settings: ResMut<GameSettings>,
+    keys: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+) {
+    let Some(slot) = rebinding.slot else {
+        return;
+    };
+
+    let key = keys.get_just_pressed().next().copied();
+    let button = gamepads
+        .iter()
+        .find_map(|gamepad| gamepad.get_just_pressed().next().copied());
+
+    let mut bindings = settings.bindings;
+    match bindings.capture(slot, key, button) {
+        Capture::Waiting => return,
+        Capture::Cancelled => {}
+        Capture::Bound(swapped) => {
+            rebinding.notice = swapped
+                .map(|other| format!("{} is now on {}", other.label(), bindings.name(other)));
+        }
+    }
+
+    rebinding.slot = None;
+
+    if bindings != settings.bindings {
+        settings.bindings = bindings;
+    }
+}
+
+fn update_binding_texts(
+    settings: Res<GameSettings>,
+    rebinding: Res<Rebinding>,
+    buttons: Query<(&BindingButton, &Children)>,
+    notices: Query<&Children, With<RebindNotice>>,
+    mut texts: Query<&mut Text>,
+) {
+    for (button, children) in &buttons {
+        let new = if rebinding.slot == Some(button.0) {
+            "...".to_string()
+        } else {
+            settings.bindings.name(button.0)
+        };
+
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child)
+                && text.0 != new
+            {
+                text.0.clone_from(&new);
+            }
+        }
+    }
+
+    let notice = match rebinding.slot {
+        Some(slot) if slot.is_gamepad() => "Press a gamepad button, a key cancels".to_string(),
+        Some(_) => "Press a key, a gamepad button or a second click cancels".to_string(),
+        None => rebinding.notice.clone().unwrap_or_default(),
+    };
+    for children in &notices {
+        for child in children {
+            if let Ok(mut text) = texts.get_mut(*child)
+                && text.0 != notice
+            {
+                text.0.clone_from(&notice);
+            }
+        }
+    }
+}
+
+fn stop_rebinding(mut rebinding: ResMut<Rebinding>) {
+    *rebinding = Rebinding::default();
+}
+
 #[derive(Component)]
 struct PauseMenuTag;
 
@@ -358,8 +807,24 @@ fn setup_score_menu(
     mut commands: Commands,
     fonts: Res<Fonts>,
     game_state: Res<GameState>,
+    high_scores: Res<HighScores>,
+    new_high_score: Option<Res<NewHighScore>>,
+    seed: Res<RunSeed>,
     handles: Res<PreLoadAssets>,
 ) {
+    let new_rank = new_high_score.as_ref().and_then(|new| new.rank);
+    let (table_title, table) = match &new_high_score {
+        Some(new) => (
+            format!(
+                "Best runs on {} ({})",
+                new.table.level,
+                new.table.mode.name()
+            ),
+            high_scores.get(&new.table),
+        ),
+        None => (String::new(), &[] as &[HighScore]),
+    };
+
     commands.spawn((
         ScoreMenuTag,
         DespawnOnExit(AppState::ScoreMenu),
@@ -377,8 +842,8 @@ fn setup_score_menu(
             },
             children![(
                 Node {
-                    width: percent(50),
-                    height: percent(70),
+                    width: percent(60),
+                    height: percent(90),
                     flex_direction: FlexDirection::Column,
                     align_items: AlignItems::Center,
                     justify_content: JustifyContent::Center,
@@ -391,7 +856,28 @@ fn setup_score_menu(
                         fonts.blue_winter.clone(),
                         36.0
                     ),
-                    text("(probably)", fonts.blue_winter.clone(), 24.0),
+                    text(
+                        if new_rank.is_some() {
+                            "(new high score!)"
+                        } else {
+                            "(probably)"
+                        },
+                        fonts.blue_winter.clone(),
+                        24.0
+                    ),
+                    text(
+                        format!(
+                            "Targets: {}  Combo: {}  Time bonus: {}",
+                            game_state.breakdown.base,
+                            game_state.breakdown.combo,
+                            game_state.breakdown.time_bonus
+                        ),
+                        fonts.blue_winter.clone(),
+                        24.0
+                    ),
+                    text(format!("Seed: {}", *seed), fonts.blue_winter.clone(), 24.0),
+                    text(table_title, fonts.blue_winter.clone(), 24.0),
+                    high_scores_ui(&fonts, table, new_rank),
                     padding(UiRect::bottom(px(48))),
                     button(
                         "Skip day",
@@ -406,6 +892,70 @@ fn setup_score_menu(
     ));
 }
 
+fn high_scores_ui(
+    fonts: &Fonts,
+    high_scores: &[HighScore],
+    new_rank: Option<usize>,
+) -> impl Bundle {
+    let header = high_score_row(
+        ["Rank", "Score", "Round", "Date"].map(String::from),
+        fonts.blue_winter.clone(),
+        false,
+    );
+
+    let rows = high_scores
+        .iter()
+        .enumerate()
+        .map(|(rank, entry)| {
+            high_score_row(
+                [
+                    (rank + 1).to_string(),
+                    entry.score.to_string(),
+                    entry.difficulty.to_string(),
+                    entry.date(),
+                ],
+                fonts.blue_winter.clone(),
+                new_rank == Some(rank),
+            )
+        })
+        .collect::<Vec<_>>();
+
+    (
+        Node {
+            width: percent(100),
+            flex_direction: FlexDirection::Column,
+            margin: UiRect::vertical(px(24)),
+            ..Default::default()
+        },
+        Children::spawn(SpawnIter(std::iter::once(header).chain(rows))),
+    )
+}
+
+fn high_score_row(cells: [String; 4], font: Handle<Font>, highlight: bool) -> impl Bundle {
+    let [rank, score, round, date] = cells;
+
+    (
+        Node {
+            display: Display::Grid,
+            grid_template_columns: RepeatedGridTrack::fr(4, 1.0),
+            justify_items: JustifyItems::Center,
+            border_radius: BorderRadius::all(px(10)),
+            ..Default::default()
+        },
+        BackgroundColor(if highlight {
+            NEW_HIGH_SCORE_COLOR
+        } else {
+            Color::NONE
+        }),
+        children![
+            text(rank, font.clone(), 24.0),
+            text(score, font.clone(), 24.0),
+            text(round, font.clone(), 24.0),
+            text(date, font, 24.0),
+        ],
+    )
+}
+
 // ---------------------------------------------------------------------------------
 
 #[derive(Component)]
@@ -419,6 +969,12 @@ enum UiEvents {
     Pause,
     PlayingSettings,
     Resume,
+    EditSeed,
+    RandomSeed,
+    NextDifficultyMode,
+    Controls,
+    PlayingControls,
+    Rebind(BindingSlot),
 }
 
 #[derive(Event)]
@@ -428,6 +984,29 @@ fn on_quit_click(_event: On<OnQuitClicked>, mut exit: MessageWriter<AppExit>) {
     exit.write(AppExit::Success);
 }
 
+#[derive(Event)]
+struct OnRandomSeedClicked;
+
+fn on_random_seed_click(
+    _event: On<OnRandomSeedClicked>,
+    mut seed: ResMut<RunSeed>,
+    mut input: ResMut<SeedInput>,
+    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<AmbientRng>>,
+) {
+    *seed = RunSeed::random(&mut rng);
+    input.0 = None;
+}
+
+#[derive(Event)]
+struct OnNextDifficultyModeClicked;
+
+fn on_next_difficulty_mode_click(
+    _event: On<OnNextDifficultyModeClicked>,
+    mut settings: ResMut<GameSettings>,
+) {
+    settings.difficulty_mode = settings.difficulty_mode.next();
+}
+
 #[derive(Event)]
 struct OnSettingsChanged(GameSettings);
 
@@ -439,24 +1018,20 @@ fn on_settings_changed(
     next_menu_state: Option<ResMut<NextState<MenuState>>>,
     next_playing_state: Option<ResMut<NextState<PlayingState>>>,
 ) {
-    *settings = GameSettings {
-        camera_x_sensitivity: event.0.camera_x_sensitivity.clamp(0.0, 2.0),
-        camera_y_sensitivity: event.0.camera_y_sensitivity.clamp(0.0, 2.0),
-        music_volume: event.0.music_volume.clamp(0.0, 100.0),
-        sfx_volume: event.0.sfx_volume.clamp(0.0, 100.0),
-    };
+    *settings = event.0.clamped();
 
     // Force UI to refresh
-    if let Some(app_state) = menu_state
-        && app_state.get() == &MenuState::Settings
-        && let Some(mut next_app_state) = next_menu_state
+    if let Some(menu_state) = menu_state
+        && let state @ (MenuState::Settings | MenuState::Controls) = menu_state.get()
+        && let Some(mut next_menu_state) = next_menu_state
     {
-        next_app_state.set(MenuState::Settings);
+        next_menu_state.set(state.clone());
     } else if let Some(playing_state) = playing_state
-        && playing_state.get() == &PlayingState::SettingsMenu
+        && let state @ (PlayingState::SettingsMenu | PlayingState::ControlsMenu) =
+            playing_state.get()
         && let Some(mut next_playing_state) = next_playing_state
     {
-        next_playing_state.set(PlayingState::SettingsMenu);
+        next_playing_state.set(state.clone());
     }
 }
 
@@ -568,6 +1143,7 @@ fn button_system(
     mut next_app_state: ResMut<NextState<AppState>>,
     mut next_menu_state: ResMut<NextState<MenuState>>,
     mut next_playing_state: ResMut<NextState<PlayingState>>,
+    mut rebinding: ResMut<Rebinding>,
 ) {
     for (entity, interaction, mut color, mut border_color, mut button, event) in
         &mut interaction_query
@@ -593,6 +1169,20 @@ fn button_system(
                     UiEvents::Resume => next_playing_state.set(PlayingState::Playing),
                     UiEvents::PlayingSettings => next_playing_state.set(PlayingState::SettingsMenu),
                     UiEvents::Pause => next_playing_state.set(PlayingState::Paused),
+                    UiEvents::EditSeed => commands.insert_resource(SeedInput(Some(String::new()))),
+                    UiEvents::RandomSeed => commands.trigger(OnRandomSeedClicked),
+                    UiEvents::NextDifficultyMode => {
+                        commands.trigger(OnNextDifficultyModeClicked);
+                    }
+                    UiEvents::Controls => next_menu_state.set(MenuState::Controls),
+                    UiEvents::PlayingControls => next_playing_state.set(PlayingState::ControlsMenu),
+                    UiEvents::Rebind(slot) => {
+                        // Clicking the slot waiting for a key again cancels
+                        *rebinding = Rebinding {
+                            slot: (rebinding.slot != Some(*slot)).then_some(*slot),
+                            notice: None,
+                        };
+                    }
                 }
 
                 commands.trigger(ButtonClicked);
@@ -0,0 +1,298 @@
+use bevy::{
+    asset::RenderAssetUsages,
+    platform::collections::HashMap,
+    prelude::*,
+    render::render_resource::{Extent3d, TextureDimension, TextureFormat},
+};
+use bevy_rerecast::{Navmesh, rerecast::PolygonNavmesh};
+
+use crate::{
+    game::{AppState, SetupState},
+    god::GodBehavior,
+    loader::LevelAssetHandles,
+    player::{Player, PlayerCameraAnchorY},
+    powerup::Powerup,
+    target::Target,
+};
+
+/// Size of the minimap on the screen, in pixels
+const FRAME_SIZE: f32 = 220.0;
+/// Pixels on the screen for each unit of the level
+const MAP_ZOOM: f32 = 1.5;
+/// Size of the texture the navmesh is drawn into
+const MAP_RESOLUTION: u32 = 256;
+const MARKER_SIZE: f32 = 10.0;
+const FILL_COLOR: Color = Color::srgba(0.9, 0.9, 0.9, 0.25);
+const OUTLINE_COLOR: Color = Color::srgb(0.9, 0.9, 0.9);
+
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(SetupState::Entities), setup)
+            .add_systems(
+                Update,
+                (follow_player, update_markers).run_if(in_state(AppState::Playing)),
+            );
+    }
+}
+
+/// Top-down view of the navmesh, kept centered on the player and turned with the camera
+#[derive(Component)]
+struct Minimap {
+    /// Largest x and z of the navmesh, drawn at the top left corner
+    max: Vec2,
+    /// Size of the level covered by the map, in units
+    extent: f32,
+}
+
+impl Minimap {
+    /// Position on the map, from 0 to 1, forward is up
+    fn to_uv(&self, position: Vec3) -> Vec2 {
+        (self.max - position.xz()) / self.extent
+    }
+
+    fn size(&self) -> f32 {
+        self.extent * MAP_ZOOM
+    }
+}
+
+/// Dot on the minimap following an entity
+#[derive(Component)]
+struct MinimapMarker(Entity);
+
+fn setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    navmeshes: Res<Assets<Navmesh>>,
+    handles: Res<LevelAssetHandles>,
+) {
+    let Some(navmesh) = navmeshes.get(&handles.navmesh) else {
+        warn!("No navmesh to draw the minimap from");
+        return;
+    };
+
+    let (vertices, polygons) = outline(&navmesh.polygon);
+    let (min, max) = vertices
+        .iter()
+        .fold((Vec2::MAX, Vec2::MIN), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+    let minimap = Minimap {
+        max,
+        extent: (max - min).max_element().max(1.0),
+    };
+
+    let uvs: Vec<Vec2> = vertices
+        .iter()
+        .map(|v| minimap.to_uv(v.extend(0.0).xzy()))
+        .collect();
+    let image = images.add(draw(&uvs, &polygons));
+
+    commands.spawn((
+        Name::new("Minimap"),
+        GlobalZIndex(-1),
+        DespawnOnExit(AppState::Playing),
+        Node {
+            position_type: PositionType::Absolute,
+            right: px(25),
+            bottom: px(25),
+            width: px(FRAME_SIZE),
+            height: px(FRAME_SIZE),
+            overflow: Overflow::clip(),
+            border_radius: BorderRadius::all(px(FRAME_SIZE * 0.5)),
+            ..Default::default()
+        },
+        BackgroundColor(Color::linear_rgba(0.0, 0.0, 0.0, 0.8)),
+        children![(
+            Node {
+                position_type: PositionType::Absolute,
+                width: px(minimap.size()),
+                height: px(minimap.size()),
+                ..Default::default()
+            },
+            ImageNode::new(image),
+            minimap,
+        )],
+    ));
+}
+
+/// Navmesh vertices seen from above and the polygons between them
+fn outline(polygon: &PolygonNavmesh) -> (Vec<Vec2>, Vec<Vec<usize>>) {
+    let origin = Vec3::from(polygon.aabb.min);
+    let cell = Vec3::new(polygon.cell_size, polygon.cell_height, polygon.cell_size);
+
+    let vertices = polygon
+        .vertices
+        .iter()
+        .map(|v| (origin + v.as_vec3() * cell).xz())
+        .collect();
+
+    let polygons = polygon
+        .polygons
+        .chunks(usize::from(polygon.max_vertices_per_polygon))
+        .map(|indices| {
+            indices
+                .iter()
+                .filter(|i| **i != PolygonNavmesh::NO_INDEX)
+                .map(|i| usize::from(*i))
+                .collect()
+        })
+        .collect();
+
+    (vertices, polygons)
+}
+
+/// Fill the polygons and trace the edges not shared with another polygon
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    clippy::cast_precision_loss
+)]
+fn draw(uvs: &[Vec2], polygons: &[Vec<usize>]) -> Image {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: MAP_RESOLUTION,
+            height: MAP_RESOLUTION,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    let resolution = MAP_RESOLUTION as f32;
+
+    let mut edges: HashMap<(usize, usize), u8> = HashMap::default();
+
+    for polygon in polygons {
+        let points: Vec<Vec2> = polygon.iter().map(|i| uvs[*i] * resolution).collect();
+        let (min, max) = points.iter().fold((Vec2::MAX, Vec2::MIN), |(min, max), p| {
+            (min.min(*p), max.max(*p))
+        });
+
+        for y in min.y.floor() as u32..max.y.ceil() as u32 {
+            for x in min.x.floor() as u32..max.x.ceil() as u32 {
+                let pixel = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                if contains(&points, pixel) {
+                    let _ = image.set_color_at(x, y, FILL_COLOR);
+                }
+            }
+        }
+
+        for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+            *edges.entry((*a.min(b), *a.max(b))).or_default() += 1;
+        }
+    }
+
+    for ((a, b), _) in edges.into_iter().filter(|(_, count)| *count == 1) {
+        let (from, to) = (uvs[a] * resolution, uvs[b] * resolution);
+        let steps = from.distance(to).ceil().max(1.0);
+
+        for step in 0..=steps as u32 {
+            let point = from.lerp(to, step as f32 / steps);
+            let _ = image.set_color_at(point.x as u32, point.y as u32, OUTLINE_COLOR);
+        }
+    }
+
+    image
+}
+
+/// Whether `point` is inside the convex polygon, whatever its winding
+fn contains(polygon: &[Vec2], point: Vec2) -> bool {
+    let sides = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(a, b)| (*b - *a).perp_dot(point - *a));
+
+    let mut positive = true;
+    let mut negative = true;
+    for side in sides {
+        positive &= side >= 0.0;
+        negative &= side <= 0.0;
+    }
+    positive || negative
+}
+
+/// Put the player at the center of the frame and turn the map with the camera
+fn follow_player(
+    minimap: Single<(&Minimap, &mut UiTransform)>,
+    player: Single<&GlobalTransform, With<Player>>,
+    anchor: Single<&Transform, With<PlayerCameraAnchorY>>,
+) {
+    let (minimap, mut transform) = minimap.into_inner();
+    let (yaw, _, _) = anchor.rotation.to_euler(EulerRot::YXZ);
+    let rotation = Rot2::radians(yaw);
+
+    // The map turns around its center, move it so the player ends up in the middle of the frame
+    let center = Vec2::splat(minimap.size() * 0.5);
+    let player = minimap.to_uv(player.translation()) * minimap.size();
+    let offset = Vec2::splat(FRAME_SIZE * 0.5) - center - rotation * (player - center);
+
+    transform.translation = Val2::px(offset.x, offset.y);
+    transform.rotation = rotation;
+}
+
+fn update_markers(
+    mut commands: Commands,
+    minimap: Single<(Entity, &Minimap)>,
+    tracked: Query<
+        (
+            Entity,
+            &GlobalTransform,
+            Has<Player>,
+            Has<Target>,
+            Has<Powerup>,
+        ),
+        Or<(With<Player>, With<Target>, With<Powerup>, With<GodBehavior>)>,
+    >,
+    mut markers: Query<(Entity, &MinimapMarker, &mut Node)>,
+) {
+    let (map, minimap) = minimap.into_inner();
+    let place = |position: Vec3| minimap.to_uv(position) * minimap.size() - MARKER_SIZE * 0.5;
+
+    let mut marked = Vec::new();
+    for (entity, marker, mut node) in &mut markers {
+        match tracked.get(marker.0) {
+            Ok((_, transform, ..)) => {
+                let point = place(transform.translation());
+                node.left = px(point.x);
+                node.top = px(point.y);
+                marked.push(marker.0);
+            }
+            Err(_) => commands.entity(entity).despawn(),
+        }
+    }
+
+    for (entity, transform, is_player, is_target, is_powerup) in &tracked {
+        if marked.contains(&entity) {
+            continue;
+        }
+
+        let color = if is_player {
+            Color::WHITE
+        } else if is_target {
+            Color::srgb(0.95, 0.75, 0.1)
+        } else if is_powerup {
+            Color::srgb(0.3, 0.85, 0.3)
+        } else {
+            Color::srgb(0.85, 0.15, 0.2)
+        };
+
+        let point = place(transform.translation());
+        commands.spawn((
+            ChildOf(map),
+            MinimapMarker(entity),
+            Node {
+                position_type: PositionType::Absolute,
+                left: px(point.x),
+                top: px(point.y),
+                width: px(MARKER_SIZE),
+                height: px(MARKER_SIZE),
+                border_radius: BorderRadius::all(px(MARKER_SIZE * 0.5)),
+                ..Default::default()
+            },
+            BackgroundColor(color),
+        ));
+    }
+}
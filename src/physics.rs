@@ -131,13 +131,16 @@ pub struct Grounded;
 pub struct MovementAcceleration {
     pub current: f32,
     pub target: f32,
+    /// Speed without any status effect
+    pub base: f32,
 }
 
 impl MovementAcceleration {
-    pub fn new(target: f32) -> Self {
+    pub fn new(base: f32) -> Self {
         Self {
-            current: target,
-            target,
+            current: base,
+            target: base,
+            base,
         }
     }
 }
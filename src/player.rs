@@ -1,3 +1,5 @@
+use std::f32::consts::{PI, TAU};
+
 use avian3d::prelude::*;
 use bevy::{
     core_pipeline::tonemapping::Tonemapping,
@@ -7,37 +9,57 @@ use bevy::{
     window::{CursorGrabMode, CursorOptions},
 };
 use bevy_enhanced_input::prelude::*;
+use serde::Deserialize;
 // use bevy_landmass::Character3dBundle;
 
 use crate::{
+    bindings::InputBindings,
+    effect::{EffectKind, StatusEffects},
+    env::EnvironmentCollider,
     game::{AppState, GameSettings, PlayingState, SetupState},
     loader::{LevelDef, PermanentAssetHandles},
+    mice::Mice,
     physics::{DAMP_FACTOR, Grounded, MaxSlopeAngle, MovementAcceleration, MovementDampingFactor},
-    target::TargetBehavior,
+    powerup::AirJumpUsed,
 };
 
 pub const PLAYER_DEFAULT_SPEED: f32 = 10.0;
 pub const PLAYER_BOOST_SPEED: f32 = PLAYER_DEFAULT_SPEED * 2.0;
 pub const PLAYER_SPEED_FACTOR: f32 = 1.0 / PLAYER_DEFAULT_SPEED;
 pub const JUMP_IMPULSE: f32 = 25.0;
+/// Room kept between the camera and the level geometry
+const CAMERA_RADIUS: f32 = 0.5;
+/// How fast the camera moves back out once clear
+const CAMERA_EASE_OUT: f32 = 4.0;
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.add_plugins(EnhancedInputPlugin)
+            .register_type::<CameraRig>()
             .init_resource::<GrabMousePlease>()
-            .add_input_context::<Player>()
+            // Evaluated every fixed tick, like the physics, so replays don't depend on the frame rate
+            .add_input_context_to::<FixedPreUpdate, Player>()
             .add_systems(OnEnter(SetupState::Entities), setup)
             .add_systems(OnEnter(PlayingState::Playing), enable_controls)
             .add_systems(OnExit(PlayingState::Playing), disable_controls)
             .add_systems(
                 Update,
-                (update_camera_pos).run_if(in_state(AppState::Playing)),
+                (
+                    rebind_player.run_if(resource_changed::<GameSettings>),
+                    reset_camera_distance.run_if(resource_changed::<GameSettings>),
+                    update_camera_pos,
+                    update_spring_arm,
+                    update_fov,
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Playing)),
             )
             .add_observer(apply_movement)
             .add_observer(apply_rotation)
             .add_observer(apply_jump)
+            .add_observer(apply_zoom)
             .add_observer(apply_toggle_menu)
             .add_observer(apply_toggle_cursor);
 
@@ -46,7 +68,12 @@ impl Plugin for PlayerPlugin {
     }
 }
 
-fn setup(mut commands: Commands, handles: Res<PermanentAssetHandles>, level_def: Res<LevelDef>) {
+fn setup(
+    mut commands: Commands,
+    handles: Res<PermanentAssetHandles>,
+    level_def: Res<LevelDef>,
+    settings: Res<GameSettings>,
+) {
     // info!("Spawning Player");
 
     let collider = Collider::capsule_endpoints(
@@ -76,54 +103,29 @@ fn setup(mut commands: Commands, handles: Res<PermanentAssetHandles>, level_def:
             LockedAxes::new().lock_rotation_x().lock_rotation_z(),
             MaxSlopeAngle(35.0f32.to_radians()),
         ),
-        TargetBehavior::Mice,
+        Mice,
         // Character3dBundle {
         //     character: todo!(),
         //     settings: todo!(),
         //     archipelago_ref: todo!(),
         // },
-        actions!(
-            Player[(
-                Action::<Movement>::new(),
-                DeadZone::default(),
-                Bindings::spawn((
-                    Cardinal::wasd_keys(), Axial::left_stick()))
-            ),
-            (
-                Action::<Rotate>::new(),
-                Bindings::spawn((
-                    Spawn((Binding::mouse_motion(), Scale::new(Vec3::new(0.1, 0.015, 0.1)), Negate::all())),
-                    Axial::right_stick().with((Scale::splat(2.0), Negate::x())),
-                )),
-            ),
-            (
-                Action::<Jump>::new(),
-                bindings![KeyCode::Space, GamepadButton::South]
-            ),
-            (
-                Action::<ToggleMenu>::new(),
-                bindings![KeyCode::Escape, GamepadButton::Start, GamepadButton::Select]
-            ),
-            // #[cfg(feature = "dev")]
-            // (
-            //     Action::<DevToggleMouseGrab>::new(),
-            //     bindings![KeyCode::Tab]
-            // )
-            ]
-        ),
-        ContextActivity::<Player>::INACTIVE
+        player_actions(&settings.bindings),
+        PlayerBindings(settings.bindings),
+        ContextActivity::<Player>::INACTIVE,
     ));
 
+    let distance = level_def.camera_distance.clamp(settings.camera_distance);
     commands.spawn((
         Name::new("Camera anchor"),
         DespawnOnExit(AppState::Playing),
         Transform::IDENTITY,
         PlayerCameraAnchorY,
+        CameraRig::default(),
         children![(
             Transform::from_rotation(Quat::from_rotation_x(10.0f32.to_radians())),
             PlayerCameraAnchorX,
             children![(
-                Transform::from_xyz(0.0, 0.0, -20.0).looking_at(Vec3::ZERO, Vec3::Y),
+                Transform::from_xyz(0.0, 0.0, -distance).looking_at(Vec3::ZERO, Vec3::Y),
                 Name::new("Player Camera"),
                 Camera3d::default(),
                 Camera {
@@ -134,50 +136,206 @@ fn setup(mut commands: Commands, handles: Res<PermanentAssetHandles>, level_def:
                 // Not sure why Bloom breaks my render
                 // Bloom::NATURAL,
                 PlayerCamera,
+                SpringArm {
+                    length: distance,
+                    distance,
+                },
             )],
         )],
     ));
 }
 
+/// Actions of the player, with the keys and buttons from the settings
+fn player_actions(input: &InputBindings) -> impl Bundle {
+    let (move_stick, look_stick) = if input.swap_sticks {
+        (Axial::right_stick(), Axial::left_stick())
+    } else {
+        (Axial::left_stick(), Axial::right_stick())
+    };
+
+    actions!(
+        Player[(
+            Action::<Movement>::new(),
+            DeadZone::default(),
+            Bindings::spawn((
+                Cardinal {
+                    north: Binding::from(input.forward),
+                    east: Binding::from(input.right),
+                    south: Binding::from(input.backward),
+                    west: Binding::from(input.left),
+                },
+                move_stick,
+            ))
+        ),
+        (
+            Action::<Rotate>::new(),
+            Bindings::spawn((
+                Spawn((Binding::mouse_motion(), Scale::new(Vec3::new(0.1, 0.015, 0.1)), Negate::all())),
+                look_stick.with((Scale::splat(2.0), Negate::x())),
+            )),
+        ),
+        (
+            Action::<Jump>::new(),
+            bindings![input.jump, input.gamepad_jump]
+        ),
+        (
+            Action::<Zoom>::new(),
+            Bindings::spawn((
+                Spawn((Binding::mouse_wheel(), SwizzleAxis::YXZ, Negate::all(), Scale::splat(2.0))),
+                Spawn((Binding::from(GamepadButton::LeftTrigger), DeltaScale::default(), Scale::splat(20.0), Negate::all())),
+                Spawn((Binding::from(GamepadButton::RightTrigger), DeltaScale::default(), Scale::splat(20.0))),
+            )),
+        ),
+        (
+            Action::<ToggleMenu>::new(),
+            bindings![input.menu, input.gamepad_menu, input.gamepad_menu_alt]
+        ),
+        // #[cfg(feature = "dev")]
+        // (
+        //     Action::<DevToggleMouseGrab>::new(),
+        //     bindings![KeyCode::Tab]
+        // )
+        ]
+    )
+}
+
+/// Bindings the actions of the player were built with
+#[derive(Component)]
+struct PlayerBindings(InputBindings);
+
+/// Rebuild the actions when the bindings change, from the pause menu
+fn rebind_player(
+    mut commands: Commands,
+    player: Single<(Entity, &mut PlayerBindings)>,
+    settings: Res<GameSettings>,
+) {
+    let (player, mut bindings) = player.into_inner();
+    if bindings.0 == settings.bindings {
+        return;
+    }
+
+    bindings.0 = settings.bindings;
+    commands
+        .entity(player)
+        .despawn_related::<Actions<Player>>()
+        .insert(player_actions(&settings.bindings));
+}
+
 fn enable_controls(
     mut commands: Commands,
     player: Single<Entity, With<Player>>,
-    mut cursor_options: Single<&mut CursorOptions>,
+    // There is no window when running headless
+    cursor_options: Option<Single<&mut CursorOptions>>,
 ) {
     commands
         .entity(player.into_inner())
         .insert(ContextActivity::<Player>::ACTIVE);
 
-    grab_cursor(&mut cursor_options, true);
+    if let Some(mut cursor_options) = cursor_options {
+        grab_cursor(&mut cursor_options, true);
+    }
     commands.insert_resource(GrabMousePlease(true));
 }
 
 fn disable_controls(
     mut commands: Commands,
     player: Single<Entity, With<Player>>,
-    mut cursor_options: Single<&mut CursorOptions>,
+    // There is no window when running headless
+    cursor_options: Option<Single<&mut CursorOptions>>,
 ) {
     commands
         .entity(player.into_inner())
         .insert(ContextActivity::<Player>::INACTIVE);
 
-    grab_cursor(&mut cursor_options, false);
+    if let Some(mut cursor_options) = cursor_options {
+        grab_cursor(&mut cursor_options, false);
+    }
     commands.insert_resource(GrabMousePlease(false));
 }
 
 /// Tag for the Player
 #[derive(Component)]
+#[require(StatusEffects)]
 pub struct Player;
 
 /// Tag for the Camera
 #[derive(Component)]
-struct PlayerCamera;
+pub struct PlayerCamera;
+
+/// Distance of the camera, shortened when the level geometry is in the way
+#[derive(Component)]
+struct SpringArm {
+    /// Distance when nothing is in the way, changed with [`Zoom`]
+    length: f32,
+    /// Current distance
+    distance: f32,
+}
+
+/// Closest and furthest the camera can be from the player on a level
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct CameraDistance {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl Default for CameraDistance {
+    fn default() -> Self {
+        Self {
+            min: 8.0,
+            max: 35.0,
+        }
+    }
+}
+
+impl CameraDistance {
+    pub fn clamp(self, distance: f32) -> f32 {
+        distance.clamp(self.min, self.max)
+    }
+}
+
+/// How the camera follows the player, on the [`PlayerCameraAnchorY`]
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct CameraRig {
+    /// How fast the camera catches up with the player, higher is stiffer
+    pub follow_damping: f32,
+    /// Seconds of the player velocity the camera looks ahead of it
+    pub look_ahead: f32,
+    /// Field of view when standing still, in degrees
+    pub fov: f32,
+    /// Extra field of view at twice the base speed, in degrees
+    pub fov_kick: f32,
+    /// Seconds without camera input before it swings back behind the player, never when `None`
+    pub recenter_delay: Option<f32>,
+    /// How fast the camera swings back behind the player
+    pub recenter_damping: f32,
+    /// Player speed under which the camera is not recentered
+    pub recenter_min_speed: f32,
+    /// Seconds since the last camera input
+    since_rotate: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            follow_damping: 12.0,
+            look_ahead: 0.15,
+            fov: 45.0,
+            fov_kick: 15.0,
+            recenter_delay: Some(2.5),
+            recenter_damping: 1.5,
+            recenter_min_speed: 2.0,
+            since_rotate: 0.0,
+        }
+    }
+}
 
 /// Tag for the Y rotation of the orbiting camera (yaw)
 ///
 /// This is used to found out what is forward as well
 #[derive(Component)]
-struct PlayerCameraAnchorY;
+pub struct PlayerCameraAnchorY;
 
 /// Tag for the X rotation of the orbiting camera (pitch)
 #[derive(Component)]
@@ -186,17 +344,22 @@ struct PlayerCameraAnchorX;
 /// Tag for the Movement inputs
 #[derive(InputAction)]
 #[action_output(Vec2)]
-struct Movement;
+pub struct Movement;
 
 /// Tag for the Rotate inputs
 #[derive(InputAction)]
 #[action_output(Vec2)]
-struct Rotate;
+pub struct Rotate;
 
 #[derive(InputAction)]
 #[action_output(bool)]
 pub struct Jump;
 
+/// Tag for the camera distance inputs, positive moves the camera away
+#[derive(InputAction)]
+#[action_output(f32)]
+pub struct Zoom;
+
 #[derive(Event)]
 pub struct PlayerJump;
 
@@ -206,7 +369,7 @@ struct DevToggleMouseGrab;
 
 #[derive(InputAction)]
 #[action_output(bool)]
-struct ToggleMenu;
+pub struct ToggleMenu;
 
 #[derive(Default, Resource)]
 struct GrabMousePlease(bool);
@@ -254,7 +417,10 @@ fn apply_movement(
 
 fn apply_rotation(
     rotate: On<Fire<Rotate>>,
-    mut anchor_y: Single<&mut Transform, (With<PlayerCameraAnchorY>, Without<PlayerCameraAnchorX>)>,
+    anchor_y: Single<
+        (&mut Transform, &mut CameraRig),
+        (With<PlayerCameraAnchorY>, Without<PlayerCameraAnchorX>),
+    >,
     mut anchor_x: Single<&mut Transform, (With<PlayerCameraAnchorX>, Without<PlayerCameraAnchorY>)>,
     cursor_options: Single<&CursorOptions>,
     settings: Res<GameSettings>,
@@ -263,6 +429,9 @@ fn apply_rotation(
         return;
     }
 
+    let (mut anchor_y, mut rig) = anchor_y.into_inner();
+    rig.since_rotate = 0.0;
+
     let (mut yaw, _, _) = anchor_y.rotation.to_euler(EulerRot::YXZ);
     let (_, mut pitch, _) = anchor_x.rotation.to_euler(EulerRot::YXZ);
 
@@ -277,14 +446,28 @@ fn apply_rotation(
 fn apply_jump(
     _: On<Start<Jump>>,
     mut commands: Commands,
-    player: Single<(&mut LinearVelocity, Has<Grounded>), With<Player>>,
+    player: Single<
+        (
+            Entity,
+            &mut LinearVelocity,
+            Has<Grounded>,
+            &StatusEffects,
+            Has<AirJumpUsed>,
+        ),
+        With<Player>,
+    >,
 ) {
-    let (mut velocity, is_grounded) = player.into_inner();
+    let (entity, mut velocity, is_grounded, effects, air_jump_used) = player.into_inner();
 
     if is_grounded {
         // info!("Player jumped");
         velocity.y += JUMP_IMPULSE;
         commands.trigger(PlayerJump);
+    } else if !air_jump_used && effects.has(EffectKind::DoubleJump) {
+        // Cancel the fall so the second jump is as high as the first
+        velocity.y = JUMP_IMPULSE;
+        commands.entity(entity).insert(AirJumpUsed);
+        commands.trigger(PlayerJump);
     }
 }
 
@@ -316,11 +499,107 @@ fn grab_cursor(cursor_options: &mut CursorOptions, grab: bool) {
 }
 
 fn update_camera_pos(
-    mut anchor: Single<&mut Transform, (With<PlayerCameraAnchorY>, Without<Player>)>,
-    player: Single<&Transform, (With<Player>, Without<PlayerCameraAnchorY>)>,
+    anchor: Single<(&mut Transform, &mut CameraRig), (With<PlayerCameraAnchorY>, Without<Player>)>,
+    player: Single<(&Transform, &LinearVelocity), (With<Player>, Without<PlayerCameraAnchorY>)>,
+    time: Res<Time>,
+) {
+    let (mut anchor, mut rig) = anchor.into_inner();
+    let (player, velocity) = player.into_inner();
+    let delta = time.delta_secs();
+    let horizontal = velocity.0.with_y(0.0);
+
+    // Lag behind the player, toward where it is heading
+    let target = player.translation + horizontal * rig.look_ahead;
+    anchor
+        .translation
+        .smooth_nudge(&target, rig.follow_damping, delta);
+
+    rig.since_rotate += delta;
+
+    let idle = rig
+        .recenter_delay
+        .is_some_and(|recenter_delay| rig.since_rotate > recenter_delay);
+    if idle && horizontal.length() > rig.recenter_min_speed {
+        // Same yaw as the one the camera looks along when moving forward
+        let (mut yaw, _, _) = anchor.rotation.to_euler(EulerRot::YXZ);
+        let behind = horizontal.x.atan2(horizontal.z);
+        let turn = (behind - yaw + PI).rem_euclid(TAU) - PI;
+
+        yaw += turn * (1.0 - (-rig.recenter_damping * delta).exp());
+        anchor.rotation = Quat::from_euler(EulerRot::YXZ, yaw, 0.0, 0.0);
+    }
+}
+
+/// Widen the view as the player speeds up past its base speed
+fn update_fov(
+    mut projection: Single<&mut Projection, With<PlayerCamera>>,
+    rig: Single<&CameraRig>,
+    player: Single<&MovementAcceleration, With<Player>>,
+) {
+    let Projection::Perspective(perspective) = projection.as_mut() else {
+        return;
+    };
+
+    let boost = (player.current / player.base - 1.0).clamp(0.0, 1.0);
+    perspective.fov = (rig.fov + rig.fov_kick * boost).to_radians();
+}
+
+fn apply_zoom(zoom: On<Fire<Zoom>>, mut arm: Single<&mut SpringArm>, level_def: Res<LevelDef>) {
+    arm.length = level_def.camera_distance.clamp(arm.length + zoom.value);
+}
+
+/// Start over from the distance picked in the settings, once it changes
+fn reset_camera_distance(
+    mut arm: Single<&mut SpringArm>,
+    settings: Res<GameSettings>,
+    level_def: Res<LevelDef>,
+    mut last: Local<Option<f32>>,
 ) {
-    // TODO: Slight lerp, so camera needs to catch up to the character
-    anchor.translation = player.translation;
+    // The other settings don't touch the zoom
+    if last.replace(settings.camera_distance) == Some(settings.camera_distance) {
+        return;
+    }
+
+    arm.length = level_def.camera_distance.clamp(settings.camera_distance);
+}
+
+/// Pull the camera in front of the walls between it and the player, and ease it back out once clear
+fn update_spring_arm(
+    camera: Single<(&mut Transform, &mut SpringArm), With<PlayerCamera>>,
+    anchor_y: Single<&Transform, (With<PlayerCameraAnchorY>, Without<PlayerCamera>)>,
+    anchor_x: Single<&Transform, (With<PlayerCameraAnchorX>, Without<PlayerCamera>)>,
+    environment: Query<(), With<EnvironmentCollider>>,
+    spatial_query: SpatialQuery,
+    time: Res<Time>,
+) {
+    let (mut transform, mut arm) = camera.into_inner();
+    let direction = Dir3::new_unchecked(anchor_y.rotation * anchor_x.rotation * Vec3::NEG_Z);
+
+    // Targets and powerups are not environment colliders, so they never push the camera
+    let clear = spatial_query
+        .cast_shape_predicate(
+            &Collider::sphere(CAMERA_RADIUS),
+            anchor_y.translation,
+            Quat::IDENTITY,
+            direction,
+            &ShapeCastConfig {
+                max_distance: arm.length,
+                ignore_origin_penetration: true,
+                ..default()
+            },
+            &SpatialQueryFilter::default(),
+            &|entity| environment.contains(entity),
+        )
+        .map_or(arm.length, |hit| hit.distance);
+
+    if clear < arm.distance {
+        arm.distance = clear;
+    } else {
+        arm.distance
+            .smooth_nudge(&clear, CAMERA_EASE_OUT, time.delta_secs());
+    }
+
+    transform.translation.z = -arm.distance;
 }
 
 // Modified version of: https://github.com/bevyengine/bevy/issues/8949#issuecomment-2254364322
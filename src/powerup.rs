@@ -1,41 +1,173 @@
 use avian3d::prelude::*;
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::Deserialize;
 
 use crate::{
+    effect::{EffectKind, Modifier, Stacking, StatusEffect, StatusEffects},
     game::{AppState, PlayingState},
-    physics::MovementAcceleration,
-    player::PLAYER_DEFAULT_SPEED,
+    physics::Grounded,
+    player::{PLAYER_BOOST_SPEED, PLAYER_DEFAULT_SPEED, Player},
+    target::{Target, TargetSystems},
 };
 
+/// Targets closer than this to the player are pulled in by the magnet
+const MAGNET_RADIUS: f32 = 20.0;
+const MAGNET_STRENGTH: f32 = 8.0;
+/// Seconds added to the round timer by [`PowerupKind::Time`]
+pub const TIME_EXTENSION: f32 = 15.0;
+/// How bright the glow of the powerup color is
+const GLOW_STRENGTH: f32 = 2.0;
+
 pub struct PowerupPlugin;
 
 impl Plugin for PowerupPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
+        app.init_resource::<PowerupMaterials>().add_systems(
             Update,
-            (tick_timers, animate).run_if(in_state(PlayingState::Playing)),
+            (
+                (tick_timers, animate, land).run_if(in_state(PlayingState::Playing)),
+                tint,
+                magnet.in_set(TargetSystems::Move),
+            ),
         );
     }
 }
 
+#[derive(Component, Reflect, Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum PowerupKind {
+    /// Run faster
+    Speed,
+    /// More time on the round timer
+    Time,
+    /// Pull the nearby targets in
+    Magnet,
+    /// Stop every target on the level
+    Freeze,
+    /// Jump once more in the air
+    DoubleJump,
+    /// Targets are worth twice their points
+    Score,
+}
+
+impl PowerupKind {
+    pub const ALL: [Self; 6] = [
+        Self::Speed,
+        Self::Time,
+        Self::Magnet,
+        Self::Freeze,
+        Self::DoubleJump,
+        Self::Score,
+    ];
+
+    pub fn model(self) -> &'static str {
+        // Only the cheese and the eye are modeled so far, the glow tells the kinds apart
+        match self {
+            Self::Time | Self::Freeze => "models/eye.glb",
+            Self::Speed | Self::Magnet | Self::DoubleJump | Self::Score => "models/cheese.glb",
+        }
+    }
+
+    /// Glow of the model, every kind has its own
+    pub fn color(self) -> Color {
+        match self {
+            Self::Speed => Color::srgb(1.0, 0.5, 0.0),
+            Self::Time => Color::srgb(0.2, 0.4, 1.0),
+            Self::Magnet => Color::srgb(1.0, 0.1, 0.1),
+            Self::Freeze => Color::srgb(0.5, 1.0, 1.0),
+            Self::DoubleJump => Color::srgb(0.2, 1.0, 0.2),
+            Self::Score => Color::srgb(0.8, 0.2, 1.0),
+        }
+    }
+
+    pub fn sound(self) -> &'static str {
+        match self {
+            Self::Speed | Self::Score => "sfx/powerup.wav",
+            Self::Time => "sfx/target.wav",
+            Self::Magnet => "sfx/laser.wav",
+            Self::Freeze => "sfx/laser2.wav",
+            Self::DoubleJump => "sfx/jump.wav",
+        }
+    }
+
+    /// Playback speed of the pickup sound, so kinds sharing a sound don't sound the same
+    pub fn pitch(self) -> f64 {
+        match self {
+            Self::Score => 1.5,
+            Self::Time | Self::DoubleJump => 1.25,
+            Self::Speed | Self::Magnet | Self::Freeze => 1.0,
+        }
+    }
+
+    /// Effect given by the powerup, `None` for the instant ones
+    pub fn effect(self) -> Option<StatusEffect> {
+        let effect = match self {
+            Self::Speed => StatusEffect::new(
+                EffectKind::Boost,
+                5.0,
+                Modifier::Speed(PLAYER_BOOST_SPEED / PLAYER_DEFAULT_SPEED),
+                Stacking::Refresh,
+            ),
+            Self::Time => return None,
+            Self::Magnet => {
+                StatusEffect::new(EffectKind::Magnet, 8.0, Modifier::None, Stacking::Refresh)
+            }
+            // Frozen targets thaw on time, catching another one does not keep them longer
+            Self::Freeze => StatusEffect::new(
+                EffectKind::Frozen,
+                3.0,
+                Modifier::Speed(0.0),
+                Stacking::Ignore,
+            ),
+            Self::DoubleJump => StatusEffect::new(
+                EffectKind::DoubleJump,
+                5.0,
+                Modifier::None,
+                Stacking::Refresh,
+            ),
+            // Each one doubles the points again
+            Self::Score => StatusEffect::new(
+                EffectKind::DoubleScore,
+                8.0,
+                Modifier::None,
+                Stacking::Stack,
+            ),
+        };
+
+        Some(effect)
+    }
+}
+
+/// Chance for a kind of powerup to spawn in a level, relative to the other kinds
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PowerupChance {
+    pub kind: PowerupKind,
+    pub weight: u32,
+}
+
 #[derive(Component)]
 pub struct Powerup;
 
+/// The model of the powerup glows with the color of its kind
 #[derive(Component)]
-struct DespawnTimer(Timer);
+struct Tinted;
+
+/// Glowing copies of the model materials, one per kind
+#[derive(Resource, Default)]
+struct PowerupMaterials(
+    HashMap<(AssetId<StandardMaterial>, PowerupKind), Handle<StandardMaterial>>,
+);
 
 #[derive(Component)]
-pub struct PowerupTimer(pub Timer);
+struct DespawnTimer(Timer);
 
-impl Default for PowerupTimer {
-    fn default() -> Self {
-        Self(Timer::from_seconds(5.0, TimerMode::Once))
-    }
-}
+/// The player already used the extra jump of [`PowerupKind::DoubleJump`] since leaving the ground
+#[derive(Component)]
+pub struct AirJumpUsed;
 
 #[derive(Bundle)]
 pub struct PowerupBundle {
     marker: Powerup,
+    kind: PowerupKind,
     collider: Collider,
     collision_event_enabled: CollisionEventsEnabled,
     mesh: SceneRoot,
@@ -46,9 +178,10 @@ pub struct PowerupBundle {
 }
 
 impl PowerupBundle {
-    pub fn new(mesh: SceneRoot, position: Vec3, name: Name) -> Self {
+    pub fn new(kind: PowerupKind, mesh: SceneRoot, position: Vec3, name: Name) -> Self {
         Self {
             marker: Powerup,
+            kind,
             collider: Collider::sphere(1.0),
             collision_event_enabled: CollisionEventsEnabled,
             mesh,
@@ -63,8 +196,6 @@ impl PowerupBundle {
 fn tick_timers(
     mut commands: Commands,
     mut despawn_timer: Query<(Entity, &mut DespawnTimer)>,
-    mut powerup_timer: Query<(Entity, &mut PowerupTimer)>,
-    mut acceleration: Query<&mut MovementAcceleration>,
     time: Res<Time>,
 ) {
     for (entity, mut timer) in &mut despawn_timer {
@@ -74,23 +205,75 @@ fn tick_timers(
             commands.entity(entity).despawn();
         }
     }
+}
 
-    for (entity, mut timer) in &mut powerup_timer {
-        timer.0.tick(time.delta());
+fn animate(mut powerup: Query<(&mut Transform, &DespawnTimer), With<Powerup>>, time: Res<Time>) {
+    for (mut transform, timer) in &mut powerup {
+        transform.translation.y = (timer.0.elapsed_secs().sin() * 0.33) + 0.8;
+        transform.rotate_local_y(5.0 * time.delta_secs());
+    }
+}
 
-        if timer.0.just_finished() {
-            commands.entity(entity).remove::<PowerupTimer>();
+/// Swap the materials of the model once it is spawned
+fn tint(
+    mut commands: Commands,
+    powerups: Query<(Entity, &PowerupKind), (With<Powerup>, Without<Tinted>)>,
+    children: Query<&Children>,
+    mut meshes: Query<&mut MeshMaterial3d<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut tinted: ResMut<PowerupMaterials>,
+) {
+    for (entity, kind) in &powerups {
+        // The scene is not spawned yet
+        if !children
+            .iter_descendants(entity)
+            .any(|child| meshes.contains(child))
+        {
+            continue;
+        }
 
-            if let Ok(mut acceleration) = acceleration.get_mut(entity) {
-                acceleration.target = PLAYER_DEFAULT_SPEED;
-            }
+        for child in children.iter_descendants(entity) {
+            let Ok(mut material) = meshes.get_mut(child) else {
+                continue;
+            };
+
+            let glowing = tinted
+                .0
+                .entry((material.id(), *kind))
+                .or_insert_with(|| {
+                    let mut glowing = materials.get(&material.0).cloned().unwrap_or_default();
+                    glowing.emissive = kind.color().to_linear() * GLOW_STRENGTH;
+                    materials.add(glowing)
+                })
+                .clone();
+
+            material.0 = glowing;
         }
+
+        commands.entity(entity).insert(Tinted);
     }
 }
 
-fn animate(mut powerup: Query<(&mut Transform, &DespawnTimer), With<Powerup>>, time: Res<Time>) {
-    for (mut transform, timer) in &mut powerup {
-        transform.translation.y = (timer.0.elapsed_secs().sin() * 0.33) + 0.8;
-        transform.rotate_local_y(5.0 * time.delta_secs());
+/// Give the extra jump back once on the ground
+fn land(mut commands: Commands, player: Query<Entity, (With<AirJumpUsed>, With<Grounded>)>) {
+    for entity in &player {
+        commands.entity(entity).remove::<AirJumpUsed>();
+    }
+}
+
+fn magnet(
+    player: Single<(&Transform, &StatusEffects), With<Player>>,
+    targets: Query<(&Transform, &mut LinearVelocity), (With<Target>, Without<Player>)>,
+) {
+    let (player, effects) = player.into_inner();
+    if !effects.has(EffectKind::Magnet) {
+        return;
+    }
+
+    for (transform, mut lin_vel) in targets {
+        let offset = (player.translation - transform.translation).with_y(0.0);
+        if offset.length() < MAGNET_RADIUS {
+            lin_vel.0 += offset.normalize_or_zero() * MAGNET_STRENGTH;
+        }
     }
 }
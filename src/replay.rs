@@ -0,0 +1,271 @@
+//! Records the player inputs of a run so it can be played back
+//!
+//! Inputs are sampled every fixed tick while playing, and fed back as [`ActionMock`]s on playback,
+//! so they go through the same observers as a human player.
+//! The player input context is updated every fixed tick, so each recorded tick is mocked
+//! for a single context update and the playback doesn't depend on the frame rate.
+//! Together with the [`RunSeed`] this is enough to replay a run, and doesn't need a window.
+
+use bevy::prelude::*;
+use bevy_enhanced_input::prelude::*;
+use thiserror::Error;
+
+use crate::{
+    game::{AppState, PlayingState},
+    player::{Jump, Movement, Rotate, ToggleMenu},
+    seed::RunSeed,
+    storage::NoStorage,
+};
+
+/// Bumped every time the binary layout changes
+pub const REPLAY_VERSION: u16 = 1;
+
+const MAGIC: &[u8; 4] = b"J7RP";
+const HEADER_SIZE: usize = MAGIC.len() + 2 + 4 + 4;
+const FRAME_SIZE: usize = 4 * 4 + 1;
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnExit(AppState::MainMenu), start_run)
+            .add_systems(
+                FixedPreUpdate,
+                play.before(EnhancedInputSystems::Update)
+                    .run_if(resource_exists::<ReplayPlayback>.and(in_state(PlayingState::Playing))),
+            )
+            .add_systems(
+                FixedUpdate,
+                record
+                    .run_if(resource_exists::<ReplayRecorder>.and(in_state(PlayingState::Playing))),
+            )
+            .add_systems(
+                OnEnter(PlayingState::GameOver),
+                save_recording.run_if(
+                    resource_exists::<ReplayRecorder>.and(not(resource_exists::<NoStorage>)),
+                ),
+            );
+    }
+}
+
+/// Action values for one fixed tick
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub struct ReplayFrame {
+    pub movement: Vec2,
+    pub rotate: Vec2,
+    pub jump: bool,
+    pub toggle_menu: bool,
+}
+
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Replay {
+    pub seed: u32,
+    pub frames: Vec<ReplayFrame>,
+}
+
+#[derive(Debug, Error)]
+pub enum ReplayError {
+    #[error("could not read replay: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("not a replay file")]
+    NotAReplay,
+    #[error("replay version {0} is not supported (expected {REPLAY_VERSION})")]
+    Version(u16),
+    #[error("replay file is truncated")]
+    Truncated,
+    #[error("replay has too many frames: {0}")]
+    TooLong(u32),
+}
+
+impl Replay {
+    /// Layout (little endian): magic, version `u16`, seed `u32`, frame count `u32`, then per frame
+    /// movement `2 * f32`, rotate `2 * f32` and a flag byte (`1` jump, `2` toggle menu)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE + self.frames.len() * FRAME_SIZE);
+
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&REPLAY_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(
+            &u32::try_from(self.frames.len())
+                .unwrap_or(u32::MAX)
+                .to_le_bytes(),
+        );
+
+        for frame in &self.frames {
+            for v in [
+                frame.movement.x,
+                frame.movement.y,
+                frame.rotate.x,
+                frame.rotate.y,
+            ] {
+                bytes.extend_from_slice(&v.to_le_bytes());
+            }
+
+            bytes.push(u8::from(frame.jump) | (u8::from(frame.toggle_menu) << 1));
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ReplayError> {
+        if bytes.len() < HEADER_SIZE || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(ReplayError::NotAReplay);
+        }
+
+        let version = u16::from_le_bytes([bytes[4], bytes[5]]);
+        if version != REPLAY_VERSION {
+            return Err(ReplayError::Version(version));
+        }
+
+        let seed = u32::from_le_bytes(bytes[6..10].try_into().unwrap());
+        let frame_count = u32::from_le_bytes(bytes[10..14].try_into().unwrap());
+        let count = usize::try_from(frame_count).map_err(|_| ReplayError::TooLong(frame_count))?;
+        let size = count
+            .checked_mul(FRAME_SIZE)
+            .ok_or(ReplayError::TooLong(frame_count))?;
+
+        let frames_bytes = &bytes[HEADER_SIZE..];
+        if frames_bytes.len() < size {
+            return Err(ReplayError::Truncated);
+        }
+
+        let frames = frames_bytes
+            .chunks_exact(FRAME_SIZE)
+            .take(count)
+            .map(|chunk| {
+                let f = |i: usize| f32::from_le_bytes(chunk[i * 4..i * 4 + 4].try_into().unwrap());
+                let flags = chunk[16];
+
+                ReplayFrame {
+                    movement: Vec2::new(f(0), f(1)),
+                    rotate: Vec2::new(f(2), f(3)),
+                    jump: flags & 1 != 0,
+                    toggle_menu: flags & 2 != 0,
+                }
+            })
+            .collect();
+
+        Ok(Self { seed, frames })
+    }
+
+    pub fn read(path: impl AsRef<std::path::Path>) -> Result<Self, ReplayError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn write(&self, path: impl AsRef<std::path::Path>) -> Result<(), ReplayError> {
+        if let Some(dir) = path.as_ref().parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(path, self.to_bytes())?;
+        Ok(())
+    }
+}
+
+/// Present while the current run is being recorded
+#[derive(Resource, Default)]
+pub struct ReplayRecorder(pub Replay);
+
+/// Insert it before a run starts to play it back instead of reading the player inputs
+///
+/// Removed once every frame was played
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    pub cursor: usize,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        Self { replay, cursor: 0 }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.cursor >= self.replay.frames.len()
+    }
+}
+
+fn start_run(
+    mut commands: Commands,
+    mut seed: ResMut<RunSeed>,
+    mut playback: Option<ResMut<ReplayPlayback>>,
+) {
+    if let Some(playback) = &mut playback {
+        // Runs before `reset_game` reseeds the rng
+        *seed = RunSeed(playback.replay.seed);
+        playback.cursor = 0;
+    } else {
+        commands.insert_resource(ReplayRecorder(Replay {
+            seed: seed.0,
+            frames: Vec::new(),
+        }));
+    }
+}
+
+fn record(
+    mut recorder: ResMut<ReplayRecorder>,
+    movement: Single<&Action<Movement>>,
+    rotate: Single<&Action<Rotate>>,
+    jump: Single<&Action<Jump>>,
+    toggle_menu: Single<&Action<ToggleMenu>>,
+) {
+    recorder.0.frames.push(ReplayFrame {
+        movement: ***movement,
+        rotate: ***rotate,
+        jump: ***jump,
+        toggle_menu: ***toggle_menu,
+    });
+}
+
+fn play(
+    mut commands: Commands,
+    mut playback: ResMut<ReplayPlayback>,
+    movement: Single<Entity, With<Action<Movement>>>,
+    rotate: Single<Entity, With<Action<Rotate>>>,
+    jump: Single<Entity, With<Action<Jump>>>,
+) {
+    let Some(frame) = playback.replay.frames.get(playback.cursor).copied() else {
+        info!("Replay finished");
+        commands.remove_resource::<ReplayPlayback>();
+        return;
+    };
+
+    playback.cursor += 1;
+
+    let mock = |pressed: bool, value: ActionValue| {
+        let state = if pressed {
+            ActionState::Fired
+        } else {
+            ActionState::None
+        };
+        ActionMock::new(state, value, MockSpan::Updates(1))
+    };
+
+    commands
+        .entity(*movement)
+        .insert(mock(frame.movement != Vec2::ZERO, frame.movement.into()));
+    commands
+        .entity(*rotate)
+        .insert(mock(frame.rotate != Vec2::ZERO, frame.rotate.into()));
+    commands
+        .entity(*jump)
+        .insert(mock(frame.jump, frame.jump.into()));
+
+    // `ToggleMenu` is recorded but not played back: nothing in a replay would resume the game
+}
+
+fn save_recording(mut commands: Commands, recorder: Res<ReplayRecorder>) {
+    #[cfg(not(feature = "web"))]
+    if let Some(path) = crate::storage::file_path("last_run.replay") {
+        match recorder.0.write(&path) {
+            Ok(()) => info!("Replay saved to {}", path.display()),
+            Err(err) => warn!("Could not save replay: {err}"),
+        }
+    }
+
+    #[cfg(feature = "web")]
+    let _ = recorder;
+
+    commands.remove_resource::<ReplayRecorder>();
+}
@@ -0,0 +1,151 @@
+use bevy::{platform::collections::HashMap, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    difficulty::DifficultyMode,
+    game::{GameSettings, GameState, PlayingState},
+    loader::LevelDef,
+    storage,
+};
+
+/// How many runs are kept in each high-score table
+pub const HIGH_SCORES_COUNT: usize = 10;
+
+pub struct ScoresPlugin;
+
+impl Plugin for ScoresPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(HighScores::load())
+            .add_systems(OnEnter(PlayingState::GameOver), record_run);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: u32,
+    /// Highest difficulty reached during the run
+    pub difficulty: u8,
+    /// Seconds since the unix epoch
+    pub date: u64,
+}
+
+impl HighScore {
+    /// Date of the run, formatted as `YYYY-MM-DD`
+    pub fn date(&self) -> String {
+        format_date(self.date)
+    }
+}
+
+/// Level and difficulty mode a high-score table is kept for
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct HighScoreTable {
+    /// Level the run ended on
+    pub level: String,
+    pub mode: DifficultyMode,
+}
+
+/// Best runs of each table, sorted from the highest score to the lowest
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub struct HighScores(HashMap<HighScoreTable, Vec<HighScore>>);
+
+impl HighScores {
+    const STORAGE_KEY: &str = "high_scores";
+    const STORAGE_VERSION: u32 = 2;
+
+    fn load() -> Self {
+        storage::load::<Self>(Self::STORAGE_KEY, Self::STORAGE_VERSION).unwrap_or_default()
+    }
+
+    fn save(&self) {
+        storage::save(Self::STORAGE_KEY, Self::STORAGE_VERSION, self);
+    }
+
+    /// Best runs of `table`, empty if none was recorded yet
+    pub fn get(&self, table: &HighScoreTable) -> &[HighScore] {
+        self.0.get(table).map_or(&[], Vec::as_slice)
+    }
+
+    /// Insert a run in `table`, returns its rank if it made it in
+    pub fn insert(&mut self, table: HighScoreTable, entry: HighScore) -> Option<usize> {
+        let entries = self.0.entry(table).or_default();
+        let rank = entries
+            .iter()
+            .position(|other| entry.score > other.score)
+            .unwrap_or(entries.len());
+
+        if rank >= HIGH_SCORES_COUNT {
+            return None;
+        }
+
+        entries.insert(rank, entry);
+        entries.truncate(HIGH_SCORES_COUNT);
+
+        Some(rank)
+    }
+}
+
+/// Table of the last run in [`HighScores`], with its rank if it made it in
+#[derive(Resource)]
+pub struct NewHighScore {
+    pub table: HighScoreTable,
+    pub rank: Option<usize>,
+}
+
+fn record_run(
+    mut commands: Commands,
+    mut high_scores: ResMut<HighScores>,
+    game_state: Res<GameState>,
+    level_def: Res<LevelDef>,
+    settings: Res<GameSettings>,
+    no_storage: Option<Res<storage::NoStorage>>,
+) {
+    let table = HighScoreTable {
+        level: level_def.prefix.clone(),
+        mode: settings.difficulty_mode,
+    };
+    let rank = high_scores.insert(
+        table.clone(),
+        HighScore {
+            score: game_state.score,
+            difficulty: game_state.difficulty,
+            date: now(),
+        },
+    );
+
+    if rank.is_some() && no_storage.is_none() {
+        high_scores.save();
+    }
+
+    commands.insert_resource(NewHighScore { table, rank });
+}
+
+#[cfg(not(feature = "web"))]
+fn now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// `SystemTime::now` panics on wasm
+#[cfg(feature = "web")]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn now() -> u64 {
+    (js_sys::Date::now() / 1000.0) as u64
+}
+
+// Days to civil date from: https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+fn format_date(secs: u64) -> String {
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
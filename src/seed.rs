@@ -0,0 +1,85 @@
+use bevy::prelude::*;
+use bevy_prng::ChaCha20Rng;
+use bevy_rand::global::GlobalRng;
+use rand::{Rng, SeedableRng};
+
+pub struct SeedPlugin;
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup);
+    }
+}
+
+/// Seed of the current run, reseeds the [`GlobalRng`] every time a new run starts
+///
+/// The same seed gives the same level order, target spawns and powerup spawns.
+/// Only the level order and the target spawns are pulled from the [`GlobalRng`],
+/// so they stay the same whatever happens during a round.
+#[derive(Resource, Clone, Copy, PartialEq, Eq)]
+pub struct RunSeed(pub u32);
+
+impl RunSeed {
+    pub fn random(rng: &mut ChaCha20Rng) -> Self {
+        Self(rng.next_u32())
+    }
+
+    pub fn rng(self) -> ChaCha20Rng {
+        ChaCha20Rng::seed_from_u64(u64::from(self.0))
+    }
+}
+
+impl std::fmt::Display for RunSeed {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:08X}", self.0)
+    }
+}
+
+impl std::str::FromStr for RunSeed {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u32::from_str_radix(s, 16).map(Self)
+    }
+}
+
+/// Tag for the rng used by everything which doesn't change the gameplay (music, menu...)
+///
+/// Those are pulled at frame dependent times, using the [`GlobalRng`] would make runs diverge
+#[derive(Component)]
+pub struct AmbientRng;
+
+/// Tag for the rng of the target AI, forked from the [`GlobalRng`] each time a run starts
+///
+/// Targets pick their goals all along the run, they must not pull from the [`GlobalRng`]
+#[derive(Component)]
+pub struct TargetRng;
+
+impl TargetRng {
+    pub fn fork(global: &mut ChaCha20Rng) -> ChaCha20Rng {
+        ChaCha20Rng::from_rng(global)
+    }
+}
+
+/// Tag for the rng of the powerup spawns, forked from the [`GlobalRng`] each time a run starts
+///
+/// Powerups spawn on a timer, how many spawn in a round depends on how fast the player is
+#[derive(Component)]
+pub struct PowerupRng;
+
+impl PowerupRng {
+    pub fn fork(global: &mut ChaCha20Rng) -> ChaCha20Rng {
+        ChaCha20Rng::from_rng(global)
+    }
+}
+
+fn setup(mut commands: Commands, mut global: Single<&mut ChaCha20Rng, With<GlobalRng>>) {
+    let mut ambient = ChaCha20Rng::from_rng(&mut **global);
+    let targets = TargetRng::fork(&mut global);
+    let powerups = PowerupRng::fork(&mut global);
+
+    commands.insert_resource(RunSeed::random(&mut ambient));
+    commands.spawn((Name::new("Ambient RNG"), AmbientRng, ambient));
+    commands.spawn((Name::new("Target RNG"), TargetRng, targets));
+    commands.spawn((Name::new("Powerup RNG"), PowerupRng, powerups));
+}
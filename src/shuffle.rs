@@ -18,6 +18,11 @@ impl<T: Clone> Shuffle<T> {
         }
     }
 
+    /// Start over from the full list
+    pub fn reset(&mut self) {
+        self.remaining = self.default.clone();
+    }
+
     pub fn next(&mut self, rng: &mut ChaCha20Rng) -> T {
         if self.remaining.is_empty() {
             self.remaining = self.default.clone();
@@ -0,0 +1,144 @@
+use std::f32::consts::PI;
+
+use avian3d::prelude::LinearVelocity;
+use bevy::prelude::*;
+
+use crate::{
+    anim::GodBone,
+    game::PlayingState,
+    god::NecromancerGod,
+    player::Player,
+    target::{IdleTimer, TargetBehavior, TargetBehaviorPlugin, TargetSystems},
+};
+
+/// Steps per second of the skeleton gait
+const GAIT_FREQUENCY: f32 = 2.5;
+/// Height of the skeleton once collapsed, as a fraction of its standing height
+const COLLAPSED_HEIGHT: f32 = 0.2;
+
+pub struct SkeletonPlugin;
+
+impl Plugin for SkeletonPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(TargetBehaviorPlugin::<Skeleton>::default())
+            .add_systems(
+                Update,
+                (
+                    shamble.in_set(TargetSystems::Move),
+                    (orient_skeleton, collapse_when_idle).in_set(TargetSystems::Animate),
+                    necromancer_follow_player.run_if(in_state(PlayingState::Playing)),
+                ),
+            );
+    }
+}
+
+/// Tag for the skeleton behavior
+#[derive(Component, Default)]
+pub struct Skeleton;
+
+impl TargetBehavior for Skeleton {
+    const NAME: &'static str = "Skeleton";
+}
+
+/// Offset the gait so the skeletons don't all step together
+#[allow(clippy::cast_precision_loss)]
+fn gait_phase(entity: Entity) -> f32 {
+    (entity.to_bits() % 16) as f32 / 16.0 * 2.0 * PI
+}
+
+/// Skeletons lurch forward on each step instead of running at a constant speed
+fn shamble(
+    query: Query<(Entity, &mut LinearVelocity), (With<Skeleton>, Without<IdleTimer>)>,
+    time: Res<Time>,
+) {
+    for (entity, mut lin_vel) in query {
+        let step = (time.elapsed_secs() * GAIT_FREQUENCY * 2.0 * PI + gait_phase(entity)).sin();
+        lin_vel.0 *= 0.6 + 0.4 * step.abs();
+    }
+}
+
+fn orient_skeleton(
+    query: Query<(&mut Transform, &LinearVelocity), With<Skeleton>>,
+    time: Res<Time>,
+) {
+    for (mut transform, lin_vel) in query {
+        let vel = lin_vel.0.with_y(0.0);
+        if vel.length_squared() < 1.0 {
+            continue;
+        }
+
+        // Slower to turn than the mice
+        transform.rotation = transform.rotation.rotate_towards(
+            Quat::from_rotation_arc(Vec3::Z, vel.normalize()),
+            180.0f32.to_radians() * time.delta_secs(),
+        );
+    }
+}
+
+/// Authored transform of a skeleton scene node, the collapse and the sway are applied on top of it
+#[derive(Component)]
+struct RestPose {
+    transform: Transform,
+    /// Current height, as a fraction of the standing height
+    height: f32,
+}
+
+/// Fall into a pile of bones while idle, then reassemble before walking again
+///
+/// Only the scene children are animated, so the collider stays untouched
+fn collapse_when_idle(
+    mut commands: Commands,
+    query: Query<(Entity, &Children, &LinearVelocity, Option<&IdleTimer>), With<Skeleton>>,
+    mut nodes: Query<(&mut Transform, Option<&mut RestPose>), Without<Skeleton>>,
+    time: Res<Time>,
+) {
+    for (entity, children, lin_vel, idle) in query {
+        let (height, sway) = match idle {
+            Some(timer) => {
+                let collapse = (timer.0.fraction() * PI).sin();
+                (1.0 - (1.0 - COLLAPSED_HEIGHT) * collapse, 0.0)
+            }
+            None => {
+                let step = (time.elapsed_secs() * GAIT_FREQUENCY * PI + gait_phase(entity)).sin();
+                let moving = (lin_vel.0.length() / 10.0).min(1.0);
+                (1.0, step * 0.15 * moving)
+            }
+        };
+
+        for child in children {
+            let Ok((mut transform, rest)) = nodes.get_mut(*child) else {
+                continue;
+            };
+
+            // First seen as authored, before any change
+            let Some(mut rest) = rest else {
+                commands.entity(*child).insert(RestPose {
+                    transform: *transform,
+                    height: 1.0,
+                });
+                continue;
+            };
+
+            rest.height = rest.height.lerp(height, 10.0 * time.delta_secs());
+            transform.scale = rest.transform.scale * Vec3::new(1.0, rest.height, 1.0);
+            transform.rotation = rest.transform.rotation * Quat::from_rotation_z(sway);
+        }
+    }
+}
+
+/// The necromancer slowly turns toward the player, swaying as if chanting
+fn necromancer_follow_player(
+    mut god: Single<(&mut Transform, &GodBone), (With<NecromancerGod>, Without<Player>)>,
+    player: Single<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    let sway = Quat::from_euler(
+        EulerRot::XYZ,
+        (time.elapsed_secs() * 0.7).sin() * 0.1,
+        0.0,
+        (time.elapsed_secs() * 0.4).sin() * 0.2,
+    );
+    let aim = god.0.looking_at(player.translation, Vec3::Y).rotation * sway * god.1.0;
+
+    god.0.rotation = god.0.rotation.slerp(aim, 1.5 * time.delta_secs());
+}
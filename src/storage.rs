@@ -0,0 +1,138 @@
+//! Tiny persistence layer, files in the user config directory on native, `localStorage` on web.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+#[cfg(not(feature = "web"))]
+const APP_DIR: &str = "dreams_for_the_fever_gods";
+
+#[cfg(feature = "web")]
+const KEY_PREFIX: &str = "dreams_for_the_fever_gods";
+
+/// Keeps the stored values untouched, for tests and simulations
+///
+/// The systems saving settings, high scores or replays don't run while it exists
+#[derive(Resource)]
+pub struct NoStorage;
+
+#[derive(Deserialize)]
+struct Header {
+    version: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Versioned<T> {
+    version: u32,
+    data: T,
+}
+
+/// Load the value stored under `key`
+///
+/// Returns `None` if nothing is stored, if the stored value is corrupt, or if it was written with another `version`
+pub fn load<T: DeserializeOwned>(key: &str, version: u32) -> Option<T> {
+    let content = read(key)?;
+
+    match ron::from_str::<Header>(&content) {
+        Ok(header) if header.version == version => {}
+        Ok(header) => {
+            warn!(
+                "Ignoring stored {key}, version {} is not {version}",
+                header.version
+            );
+            return None;
+        }
+        Err(err) => {
+            warn!("Ignoring stored {key}, it is corrupt: {err}");
+            return None;
+        }
+    }
+
+    match ron::from_str::<Versioned<T>>(&content) {
+        Ok(versioned) => Some(versioned.data),
+        Err(err) => {
+            warn!("Ignoring stored {key}, it is corrupt: {err}");
+            None
+        }
+    }
+}
+
+/// Store `value` under `key`, tagged with `version`
+pub fn save<T: Serialize>(key: &str, version: u32, value: &T) {
+    let content = match ron::ser::to_string_pretty(
+        &Versioned {
+            version,
+            data: value,
+        },
+        ron::ser::PrettyConfig::default(),
+    ) {
+        Ok(content) => content,
+        Err(err) => {
+            warn!("Could not serialize {key}: {err}");
+            return;
+        }
+    };
+
+    write(key, &content);
+}
+
+/// Path of a file stored next to the saved values
+#[cfg(not(feature = "web"))]
+pub fn file_path(name: &str) -> Option<std::path::PathBuf> {
+    Some(dirs::config_dir()?.join(APP_DIR).join(name))
+}
+
+#[cfg(not(feature = "web"))]
+fn path(key: &str) -> Option<std::path::PathBuf> {
+    file_path(&format!("{key}.ron"))
+}
+
+#[cfg(not(feature = "web"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)?).ok()
+}
+
+#[cfg(not(feature = "web"))]
+fn write(key: &str, content: &str) {
+    let Some(path) = path(key) else {
+        warn!("Could not find a config directory to save {key}");
+        return;
+    };
+
+    if let Some(dir) = path.parent()
+        && let Err(err) = std::fs::create_dir_all(dir)
+    {
+        warn!("Could not create {}: {err}", dir.display());
+        return;
+    }
+
+    if let Err(err) = std::fs::write(&path, content) {
+        warn!("Could not save {key} to {}: {err}", path.display());
+    }
+}
+
+#[cfg(feature = "web")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(feature = "web")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?
+        .get_item(&format!("{KEY_PREFIX}.{key}"))
+        .ok()?
+}
+
+#[cfg(feature = "web")]
+fn write(key: &str, content: &str) {
+    let Some(storage) = local_storage() else {
+        warn!("localStorage is not available, can't save {key}");
+        return;
+    };
+
+    if storage
+        .set_item(&format!("{KEY_PREFIX}.{key}"), content)
+        .is_err()
+    {
+        warn!("Could not save {key} to localStorage");
+    }
+}
@@ -1,52 +1,181 @@
+use std::marker::PhantomData;
+
 use avian3d::prelude::*;
-use bevy::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*};
 use bevy_landmass::{
-    Agent, Agent3dBundle, AgentDesiredVelocity3d, AgentSettings, AgentTarget3d, ArchipelagoRef3d,
+    Agent, Agent3dBundle, AgentDesiredVelocity3d, AgentSettings, ArchipelagoRef3d,
     TargetReachedCondition,
 };
+use serde::Deserialize;
 
 use crate::{
-    game::{AppState, PlayingState, get_random_position_on_navmesh},
+    difficulty::DifficultyStep,
+    effect::{EffectKind, StatusEffects},
+    flee::{FleePlugin, Fleeing},
+    flocking::Flocking,
+    game::{AppState, GameState, PlayingState},
     physics::{DAMP_FACTOR, MovementAcceleration, MovementDampingFactor},
+    wander::{Progress, WanderPlugin},
+    watchdog::WatchdogPlugin,
 };
 
-const TARGET_DEFAULT_SPEED: f32 = 10.0;
-const TARGET_IDLE_TIMER: f32 = 1.5;
+pub const TARGET_DEFAULT_SPEED: f32 = 10.0;
+pub const TARGET_IDLE_TIMER: f32 = 1.5;
 
 pub struct TargetPlugin;
 
 impl Plugin for TargetPlugin {
     fn build(&self, app: &mut App) {
-        app.register_type::<IdleTimer>().add_systems(
-            Update,
-            (
-                (move_agents, tick_idle_timers).run_if(in_state(PlayingState::Playing)),
-                assign_new_target,
-            ),
-        );
+        app.add_plugins((FleePlugin, WanderPlugin, WatchdogPlugin))
+            .register_type::<IdleTimer>()
+            .register_type::<TargetScore>()
+            .register_type::<Flocking>()
+            .init_resource::<TargetBehaviors>()
+            .configure_sets(
+                Update,
+                (
+                    TargetSystems::Perceive,
+                    TargetSystems::Steer,
+                    TargetSystems::Move,
+                    TargetSystems::Animate,
+                )
+                    .chain()
+                    .run_if(in_state(PlayingState::Playing)),
+            )
+            .add_systems(
+                Update,
+                (move_agents, tick_idle_timers).in_set(TargetSystems::Steer),
+            );
     }
 }
 
+/// Shared steering runs first, then each behavior adjusts the movement and animates its targets
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TargetSystems {
+    /// Reaction to the player, before anything moves
+    Perceive,
+    /// Navmesh steering and idle timers, common to every behavior
+    Steer,
+    /// Behavior specific movement, on top of the steered velocity
+    Move,
+    /// Behavior specific animation
+    Animate,
+}
+
 #[derive(Component)]
+#[require(Progress, StatusEffects)]
 pub struct Target;
 
-#[derive(Component, Clone, Copy, PartialEq, Eq)]
-pub enum TargetBehavior {
-    Mice,
-    Skeleton,
+/// Component tagging the targets driven by a behavior
+///
+/// A behavior plugin adds a [`TargetBehaviorPlugin`] for its component and its own systems
+/// in the [`TargetSystems`] sets, filtered on it
+pub trait TargetBehavior: Component + Default {
+    /// Name used by `target_behavior` in the level definitions
+    const NAME: &'static str;
 }
 
-#[derive(Component, Reflect)]
-struct IdleTimer(Timer);
+/// Registers a behavior so levels can select it by name
+pub struct TargetBehaviorPlugin<B: TargetBehavior>(PhantomData<B>);
 
-impl Default for IdleTimer {
+impl<B: TargetBehavior> Default for TargetBehaviorPlugin<B> {
     fn default() -> Self {
-        Self(Timer::from_seconds(TARGET_IDLE_TIMER, TimerMode::Once))
+        Self(PhantomData)
     }
 }
 
+impl<B: TargetBehavior> Plugin for TargetBehaviorPlugin<B> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetBehaviors>();
+        app.world_mut()
+            .resource_mut::<TargetBehaviors>()
+            .0
+            .insert(B::NAME, |entity| {
+                entity.insert(B::default());
+            });
+    }
+}
+
+/// Registered behaviors, by name
+#[derive(Resource, Default)]
+pub struct TargetBehaviors(HashMap<&'static str, fn(&mut EntityCommands)>);
+
+impl TargetBehaviors {
+    /// Names of the registered behaviors
+    pub fn names(&self) -> Vec<&'static str> {
+        self.0.keys().copied().collect()
+    }
+
+    /// Insert the component of the behavior named `name`
+    pub fn insert(&self, name: &str, entity: &mut EntityCommands) {
+        match self.0.get(name) {
+            Some(insert) => insert(entity),
+            None => warn!("Unknown target behavior {name}"),
+        }
+    }
+}
+
+#[derive(Component, Reflect)]
+pub struct IdleTimer(pub Timer);
+
+impl IdleTimer {
+    fn new(duration: f32) -> Self {
+        Self(Timer::from_seconds(duration, TimerMode::Once))
+    }
+}
+
+/// Pick a new wander goal
 #[derive(Component)]
-struct RequestNewTarget;
+pub struct RequestNewTarget;
+
+/// Kind of target a level can spawn, set in `targets` in the `.level.ron`
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TargetVariant {
+    /// Model, inside the level folder
+    pub model: String,
+    /// Multiplies the speed of the round
+    pub speed: f32,
+    /// Multiplies the size of the model and collider
+    pub size: f32,
+    /// Points given when caught
+    pub score: u32,
+    /// Chance to spawn, relative to the other variants
+    pub weight: u32,
+}
+
+impl Default for TargetVariant {
+    fn default() -> Self {
+        Self {
+            model: "target.glb".to_string(),
+            speed: 1.0,
+            size: 1.0,
+            score: 100,
+            weight: 1,
+        }
+    }
+}
+
+/// Part of the navmesh the targets spawn and respawn in, set in `spawn_area` in the `.level.ron`
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(default)]
+pub struct SpawnArea {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl Default for SpawnArea {
+    fn default() -> Self {
+        Self {
+            center: Vec3::ZERO,
+            radius: 120.0,
+        }
+    }
+}
+
+/// Points given when the target is caught
+#[derive(Component, Reflect)]
+pub struct TargetScore(pub u32);
 
 #[derive(Bundle)]
 pub struct TargetBundle {
@@ -59,6 +188,7 @@ pub struct TargetBundle {
     damping: MovementDampingFactor,
     position_intergration: CustomPositionIntegration,
     marker: Target,
+    score: TargetScore,
     agent: Agent3dBundle,
     target_condition: TargetReachedCondition,
     idle: IdleTimer,
@@ -67,25 +197,33 @@ pub struct TargetBundle {
 }
 
 impl TargetBundle {
-    pub fn new(mesh: Handle<Scene>, position: Vec3, navmesh: Entity) -> Self {
+    pub fn new(
+        mesh: Handle<Scene>,
+        position: Vec3,
+        navmesh: Entity,
+        step: &DifficultyStep,
+        variant: &TargetVariant,
+    ) -> Self {
+        // The collider follows the scale of the transform, but not the shape caster
         let collider = Collider::capsule_endpoints(
             0.35,
             Vec3::new(0.0, 0.35 * 0.5, -0.2),
             Vec3::new(0.0, 0.35 * 0.5, -1.0),
         );
         let mut caster_shape = collider.clone();
-        caster_shape.set_scale(Vec3::ONE * 0.99, 10);
+        caster_shape.set_scale(Vec3::ONE * 0.99 * variant.size, 10);
 
         Self {
             mesh: SceneRoot(mesh),
-            transform: Transform::from_translation(position),
+            transform: Transform::from_translation(position).with_scale(Vec3::splat(variant.size)),
             rigid_body: RigidBody::Dynamic,
             collider,
             shape_caster: ShapeCaster::new(caster_shape, Vec3::ZERO, Quat::IDENTITY, Dir3::NEG_Y),
-            acceleration: MovementAcceleration::new(TARGET_DEFAULT_SPEED),
+            acceleration: MovementAcceleration::new(step.target_speed * variant.speed),
             damping: MovementDampingFactor(DAMP_FACTOR),
             position_intergration: CustomPositionIntegration,
             marker: Target,
+            score: TargetScore(variant.score),
             agent: Agent3dBundle {
                 agent: Agent::default(),
                 settings: AgentSettings {
@@ -96,7 +234,7 @@ impl TargetBundle {
                 archipelago_ref: ArchipelagoRef3d::new(navmesh),
             },
             target_condition: TargetReachedCondition::Distance(Some(5.0)),
-            idle: IdleTimer::default(),
+            idle: IdleTimer::new(step.target_idle),
             name: Name::new("Target"),
             despawn: DespawnOnExit(AppState::Playing),
         }
@@ -105,23 +243,72 @@ impl TargetBundle {
 
 fn move_agents(
     mut commands: Commands,
-    agent: Query<(
-        Entity,
-        &mut LinearVelocity,
-        &MovementAcceleration,
-        &AgentDesiredVelocity3d,
-        Has<IdleTimer>,
-    )>,
+    mut agent: Query<
+        (
+            Entity,
+            &Transform,
+            &mut LinearVelocity,
+            &mut MovementAcceleration,
+            &AgentDesiredVelocity3d,
+            &StatusEffects,
+            Has<IdleTimer>,
+            Has<Fleeing>,
+        ),
+        With<Target>,
+    >,
+    game_state: Res<GameState>,
+    flocking: Option<Res<Flocking>>,
 ) {
-    for (entity, mut lin_vel, max_acceleration, desired_vel, has_timer) in agent {
-        lin_vel.0 = desired_vel.velocity().normalize_or_zero() * max_acceleration.current;
+    // Position and heading of every target, for the flocking
+    let neighbors = if flocking.is_some() {
+        agent
+            .iter()
+            .map(|(_, transform, _, _, desired_vel, ..)| {
+                (
+                    transform.translation,
+                    desired_vel.velocity().normalize_or_zero(),
+                )
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    for (
+        entity,
+        transform,
+        mut lin_vel,
+        mut max_acceleration,
+        desired_vel,
+        effects,
+        has_timer,
+        fleeing,
+    ) in &mut agent
+    {
+        // Targets change speed right away, unlike the player
+        max_acceleration.current = max_acceleration.target;
+
+        let mut direction = desired_vel.velocity().normalize_or_zero();
+
+        if let Some(flocking) = &flocking
+            && direction != Vec3::ZERO
+        {
+            direction = (direction + flocking.steer(transform.translation, &neighbors))
+                .normalize_or(direction);
+        }
+
+        lin_vel.0 = direction * max_acceleration.current;
 
         let current_speed = lin_vel.length();
         if current_speed < 1.0 {
             lin_vel.0 = Vec3::ZERO;
 
-            if !has_timer {
-                commands.entity(entity).insert(IdleTimer::default());
+            // Fleeing targets get a new goal from the flee systems instead,
+            // frozen ones keep theirs until they thaw
+            if !has_timer && !fleeing && !effects.has(EffectKind::Frozen) {
+                commands
+                    .entity(entity)
+                    .insert(IdleTimer::new(game_state.step.target_idle));
             }
         }
     }
@@ -143,25 +330,3 @@ fn tick_idle_timers(
         }
     }
 }
-
-fn assign_new_target(
-    mut commands: Commands,
-    query: Query<(Entity, &Transform), Added<RequestNewTarget>>,
-    navmesh: Single<&bevy_landmass::Archipelago3d>,
-    mut rng: Single<&mut bevy_prng::ChaCha20Rng, With<bevy_rand::global::GlobalRng>>,
-) {
-    for (entity, transform) in query {
-        let Ok(target) =
-            get_random_position_on_navmesh(transform.translation, 80.0, &navmesh, &mut rng)
-        else {
-            warn!("Could not get new target for {entity}");
-            return;
-        };
-
-        // assign new target
-        commands
-            .entity(entity)
-            .remove::<RequestNewTarget>()
-            .insert(AgentTarget3d::Point(target.point()));
-    }
-}
@@ -0,0 +1,202 @@
+use bevy::{
+    ecs::{lifecycle::HookContext, system::SystemParam, world::DeferredWorld},
+    prelude::*,
+};
+use bevy_landmass::{AgentTarget3d, Archipelago3d};
+
+use crate::{
+    effect::{EffectKind, StatusEffects},
+    flee::Fleeing,
+    game::{get_random_position_on_navmesh, sample_position_on_navmesh},
+    seed::TargetRng,
+    target::{IdleTimer, RequestNewTarget, TargetSystems},
+};
+
+/// How far from their position targets look for a new goal
+const WANDER_RADIUS: f32 = 80.0;
+/// Number of goals considered each time a target needs a new one
+const WANDER_CANDIDATES: usize = 8;
+/// Distances at which the navmesh around a goal is probed, the goals with the most room win
+const BORDER_PROBES: [f32; 3] = [2.0, 4.0, 8.0];
+/// How often the progress of the targets is checked, in seconds
+const PROGRESS_TIMER: f32 = 3.0;
+/// Targets which moved less than this since the last check are considered stuck
+const PROGRESS_MIN_DISTANCE: f32 = 2.0;
+/// How long targets wait before looking for a goal again when none was reachable, in seconds
+const WANDER_RETRY: f32 = 1.0;
+
+pub struct WanderPlugin;
+
+impl Plugin for WanderPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Progress>().add_systems(
+            Update,
+            (
+                assign_wander_goal,
+                watch_progress.in_set(TargetSystems::Steer),
+            ),
+        );
+    }
+}
+
+/// Picks wander goals the targets can actually reach
+#[derive(SystemParam)]
+pub struct WanderGoals<'w, 's> {
+    navmesh: Single<'w, 's, &'static Archipelago3d>,
+    rng: Single<'w, 's, &'static mut bevy_prng::ChaCha20Rng, With<TargetRng>>,
+}
+
+impl WanderGoals<'_, '_> {
+    /// Random reachable goal around `from`, away from the navmesh border when possible
+    pub fn pick(&mut self, from: Vec3, radius: f32) -> Option<Vec3> {
+        let navmesh: &Archipelago3d = &self.navmesh;
+        let start = sample_position_on_navmesh(from, 5.0, navmesh).ok()?;
+
+        (0..WANDER_CANDIDATES)
+            .filter_map(|_| {
+                get_random_position_on_navmesh(from, radius, navmesh, &mut self.rng).ok()
+            })
+            .filter(|goal| navmesh.find_path(&start, goal, &default()).is_ok())
+            .map(|goal| (goal.point(), border_clearance(goal.point(), navmesh)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(goal, _)| goal)
+    }
+
+    /// Random point around `center` with the most room around it, the path does not matter
+    pub fn open_point(&mut self, center: Vec3, radius: f32) -> Option<Vec3> {
+        let navmesh: &Archipelago3d = &self.navmesh;
+
+        (0..WANDER_CANDIDATES)
+            .filter_map(|_| {
+                get_random_position_on_navmesh(center, radius, navmesh, &mut self.rng).ok()
+            })
+            .map(|point| (point.point(), border_clearance(point.point(), navmesh)))
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(point, _)| point)
+    }
+}
+
+/// Rough distance from `point` to the navmesh border, up to the last of [`BORDER_PROBES`]
+fn border_clearance(point: Vec3, navmesh: &Archipelago3d) -> f32 {
+    BORDER_PROBES
+        .into_iter()
+        .take_while(|distance| {
+            (0..8u8).all(|i| {
+                let direction =
+                    Quat::from_rotation_y(f32::from(i) * std::f32::consts::FRAC_PI_4) * Vec3::X;
+                sample_position_on_navmesh(point + direction * *distance, 0.5, navmesh).is_ok()
+            })
+        })
+        .last()
+        .unwrap_or(0.0)
+}
+
+/// Where the target was at the last progress check
+#[derive(Component, Reflect)]
+#[component(on_add = start_from_spawn)]
+pub struct Progress {
+    last_position: Vec3,
+    timer: Timer,
+    /// Number of checks in a row the target was stuck at, new goals did not help if it keeps growing
+    pub stuck_checks: u8,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Self {
+            last_position: Vec3::ZERO,
+            timer: Timer::from_seconds(PROGRESS_TIMER, TimerMode::Repeating),
+            stuck_checks: 0,
+        }
+    }
+}
+
+impl Progress {
+    /// Start over from `position`, after a teleport
+    pub fn reset(&mut self, position: Vec3) {
+        *self = Self {
+            last_position: position,
+            ..default()
+        };
+    }
+}
+
+/// The first check measures from where the target spawned
+fn start_from_spawn(mut world: DeferredWorld, context: HookContext) {
+    let Some(position) = world
+        .get::<Transform>(context.entity)
+        .map(|transform| transform.translation)
+    else {
+        return;
+    };
+
+    if let Some(mut progress) = world.get_mut::<Progress>(context.entity) {
+        progress.last_position = position;
+    }
+}
+
+fn assign_wander_goal(
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), With<RequestNewTarget>>,
+    mut goals: WanderGoals,
+) {
+    for (entity, transform) in query {
+        // Wait a bit before trying again if nothing reachable was found
+        let Some(goal) = goals.pick(transform.translation, WANDER_RADIUS) else {
+            warn!("Could not get new target for {entity}, retrying in {WANDER_RETRY}s");
+            commands
+                .entity(entity)
+                .remove::<RequestNewTarget>()
+                .insert(IdleTimer(Timer::from_seconds(
+                    WANDER_RETRY,
+                    TimerMode::Once,
+                )));
+            continue;
+        };
+
+        // assign new target
+        commands
+            .entity(entity)
+            .remove::<RequestNewTarget>()
+            .insert(AgentTarget3d::Point(goal));
+    }
+}
+
+/// Targets walking for a while without getting anywhere get a new goal
+fn watch_progress(
+    mut commands: Commands,
+    query: Query<
+        (Entity, &Transform, &mut Progress, &StatusEffects),
+        (
+            Without<IdleTimer>,
+            Without<RequestNewTarget>,
+            Without<Fleeing>,
+        ),
+    >,
+    time: Res<Time>,
+) {
+    for (entity, transform, mut progress, effects) in query {
+        // Not moving while frozen is expected, start over once thawed
+        if effects.has(EffectKind::Frozen) {
+            progress.reset(transform.translation);
+            continue;
+        }
+
+        progress.timer.tick(time.delta());
+
+        if !progress.timer.just_finished() {
+            continue;
+        }
+
+        let moved = transform.translation.distance(progress.last_position);
+        progress.last_position = transform.translation;
+
+        if moved < PROGRESS_MIN_DISTANCE {
+            // info!("{entity} is stuck, picking a new goal");
+            progress.stuck_checks = progress.stuck_checks.saturating_add(1);
+            commands.entity(entity).insert(RequestNewTarget);
+        } else {
+            progress.stuck_checks = 0;
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::time::Duration;
+
+use avian3d::prelude::*;
+use bevy::{platform::collections::HashMap, prelude::*, time::common_conditions::on_timer};
+use bevy_landmass::AgentTarget3d;
+
+use crate::{
+    env::EnvironmentCollider,
+    loader::LevelDef,
+    target::{IdleTimer, RequestNewTarget, Target, TargetSystems},
+    wander::{Progress, WanderGoals},
+};
+
+/// Failed progress checks in a row before a target is respawned
+const MAX_STUCK_CHECKS: u8 = 3;
+/// How often targets are checked for being inside the level colliders, in milliseconds
+const INSIDE_CHECK_INTERVAL: u64 = 1000;
+/// Height of the point tested against the level colliders, to stay clear of the ground
+const INSIDE_CHECK_HEIGHT: f32 = 0.35;
+
+pub struct WatchdogPlugin;
+
+impl Plugin for WatchdogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TargetRespawns>()
+            .add_systems(
+                Update,
+                (
+                    check_no_progress,
+                    check_inside_collider
+                        .run_if(on_timer(Duration::from_millis(INSIDE_CHECK_INTERVAL))),
+                )
+                    .in_set(TargetSystems::Steer),
+            )
+            .add_observer(count_respawns);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StuckReason {
+    /// New goals did not get the target moving again
+    NoProgress,
+    /// The target ended up inside the level geometry
+    InsideCollider,
+}
+
+/// A stuck target was teleported somewhere else on the navmesh
+#[derive(Event, Debug)]
+pub struct TargetRespawned {
+    pub entity: Entity,
+    pub level: String,
+    pub from: Vec3,
+    pub to: Vec3,
+    pub reason: StuckReason,
+}
+
+/// Number of respawned targets per level, since the game started
+#[derive(Resource, Default, Debug)]
+pub struct TargetRespawns(pub HashMap<String, u32>);
+
+fn check_no_progress(
+    mut commands: Commands,
+    query: Query<(Entity, &mut Transform, &mut Progress), With<Target>>,
+    mut goals: WanderGoals,
+    level_def: Res<LevelDef>,
+) {
+    for (entity, mut transform, mut progress) in query {
+        if progress.stuck_checks < MAX_STUCK_CHECKS {
+            continue;
+        }
+
+        respawn(
+            &mut commands,
+            entity,
+            &mut transform,
+            &mut progress,
+            &mut goals,
+            &level_def,
+            StuckReason::NoProgress,
+        );
+    }
+}
+
+fn check_inside_collider(
+    mut commands: Commands,
+    query: Query<(Entity, &mut Transform, &mut Progress), With<Target>>,
+    environment: Query<(), With<EnvironmentCollider>>,
+    spatial_query: SpatialQuery,
+    mut goals: WanderGoals,
+    level_def: Res<LevelDef>,
+) {
+    for (entity, mut transform, mut progress) in query {
+        let point = transform.translation + Vec3::Y * INSIDE_CHECK_HEIGHT;
+
+        let inside = spatial_query
+            .point_intersections(point, &SpatialQueryFilter::default())
+            .into_iter()
+            .any(|hit| environment.contains(hit));
+
+        if inside {
+            respawn(
+                &mut commands,
+                entity,
+                &mut transform,
+                &mut progress,
+                &mut goals,
+                &level_def,
+                StuckReason::InsideCollider,
+            );
+        }
+    }
+}
+
+fn respawn(
+    commands: &mut Commands,
+    entity: Entity,
+    transform: &mut Transform,
+    progress: &mut Progress,
+    goals: &mut WanderGoals,
+    level_def: &LevelDef,
+    reason: StuckReason,
+) {
+    let from = transform.translation;
+    let area = level_def.spawn_area;
+
+    // Anywhere the target can still walk to beats leaving it stuck
+    let Some(to) = goals
+        .open_point(area.center, area.radius)
+        .or_else(|| goals.pick(from, area.radius))
+    else {
+        warn!("Could not find a respawn point for {entity}, trying again later");
+        // Start counting from scratch instead of trying again every frame
+        progress.reset(from);
+        return;
+    };
+
+    transform.translation = to;
+    progress.reset(to);
+
+    commands
+        .entity(entity)
+        .remove::<(AgentTarget3d, IdleTimer)>()
+        .insert((LinearVelocity::ZERO, RequestNewTarget));
+
+    commands.trigger(TargetRespawned {
+        entity,
+        level: level_def.prefix.clone(),
+        from,
+        to,
+        reason,
+    });
+}
+
+fn count_respawns(event: On<TargetRespawned>, mut respawns: ResMut<TargetRespawns>) {
+    let count = respawns.0.entry(event.level.clone()).or_default();
+    *count += 1;
+
+    info!(
+        "Respawned {} ({:?}) from {} to {}, {} respawns so far in {}",
+        event.entity, event.reason, event.from, event.to, count, event.level
+    );
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use jam7::bindings::{BindingSlot, Capture, InputBindings};
+
+#[test]
+fn taken_key_is_swapped() {
+    let mut bindings = InputBindings::default();
+
+    let capture = bindings.capture(BindingSlot::Jump, Some(KeyCode::KeyW), None);
+
+    assert_eq!(capture, Capture::Bound(Some(BindingSlot::Forward)));
+    assert_eq!(bindings.jump, KeyCode::KeyW);
+    assert_eq!(bindings.forward, KeyCode::Space);
+}
+
+#[test]
+fn taken_button_is_swapped() {
+    let mut bindings = InputBindings::default();
+
+    let capture = bindings.capture(BindingSlot::GamepadMenu, None, Some(GamepadButton::Select));
+
+    assert_eq!(capture, Capture::Bound(Some(BindingSlot::GamepadMenuAlt)));
+    assert_eq!(bindings.gamepad_menu, GamepadButton::Select);
+    assert_eq!(bindings.gamepad_menu_alt, GamepadButton::Start);
+}
+
+#[test]
+fn free_key_is_bound() {
+    let mut bindings = InputBindings::default();
+
+    let capture = bindings.capture(BindingSlot::Jump, Some(KeyCode::KeyJ), None);
+
+    assert_eq!(capture, Capture::Bound(None));
+    assert_eq!(bindings.jump, KeyCode::KeyJ);
+    assert_eq!(bindings.menu, KeyCode::Escape);
+}
+
+#[test]
+fn escape_can_be_bound() {
+    let mut bindings = InputBindings::default();
+    bindings.capture(BindingSlot::Menu, Some(KeyCode::KeyP), None);
+
+    let capture = bindings.capture(BindingSlot::Forward, Some(KeyCode::Escape), None);
+
+    assert_eq!(capture, Capture::Bound(None));
+    assert_eq!(bindings.forward, KeyCode::Escape);
+}
+
+#[test]
+fn other_kind_of_input_cancels() {
+    let mut bindings = InputBindings::default();
+
+    assert_eq!(
+        bindings.capture(BindingSlot::Jump, None, Some(GamepadButton::East)),
+        Capture::Cancelled
+    );
+    assert_eq!(
+        bindings.capture(BindingSlot::GamepadJump, Some(KeyCode::Escape), None),
+        Capture::Cancelled
+    );
+    assert_eq!(bindings, InputBindings::default());
+}
+
+#[test]
+fn nothing_pressed_keeps_waiting() {
+    let mut bindings = InputBindings::default();
+
+    assert_eq!(
+        bindings.capture(BindingSlot::Jump, None, None),
+        Capture::Waiting
+    );
+    assert_eq!(bindings, InputBindings::default());
+}
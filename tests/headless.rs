@@ -0,0 +1,90 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use jam7::{
+    game::{AppState, GameState, PlayingState},
+    headless::headless_app,
+    target::Target,
+};
+
+const STEP: Duration = Duration::from_millis(100);
+const MAX_UPDATES: usize = 5_000;
+
+fn app_state(app: &App) -> AppState {
+    app.world().resource::<State<AppState>>().get().clone()
+}
+
+fn update_until(app: &mut App, mut done: impl FnMut(&mut App) -> bool) {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+
+        if done(app) {
+            return;
+        }
+    }
+
+    panic!(
+        "Gave up after {MAX_UPDATES} updates, stuck in {:?}",
+        app_state(app)
+    );
+}
+
+fn start_run(app: &mut App) {
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Loading);
+}
+
+fn play_until_game_over(app: &mut App) {
+    update_until(app, |app| {
+        app.world()
+            .get_resource::<State<PlayingState>>()
+            .is_some_and(|state| *state == PlayingState::Playing)
+    });
+
+    update_until(app, |app| app_state(app) == AppState::ScoreMenu);
+}
+
+#[test]
+fn run_goes_through_every_state() {
+    let mut app = headless_app(STEP);
+    app.update();
+    assert_eq!(app_state(&app), AppState::MainMenu);
+
+    start_run(&mut app);
+    update_until(&mut app, |app| app_state(app) == AppState::Playing);
+
+    let targets = app
+        .world_mut()
+        .query_filtered::<(), With<Target>>()
+        .iter(app.world())
+        .count();
+    let game_state = app.world().resource::<GameState>();
+    assert_eq!(game_state.difficulty, 1);
+    assert_eq!(targets, usize::from(game_state.total_targets));
+
+    play_until_game_over(&mut app);
+}
+
+#[test]
+fn restarting_after_losing_does_not_crash() {
+    let mut app = headless_app(STEP);
+    app.update();
+
+    for _ in 0..2 {
+        start_run(&mut app);
+        play_until_game_over(&mut app);
+
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::MainMenu);
+        update_until(&mut app, |app| app_state(app) == AppState::MainMenu);
+
+        let targets = app
+            .world_mut()
+            .query_filtered::<(), With<Target>>()
+            .iter(app.world())
+            .count();
+        assert_eq!(targets, 0, "Targets should be despawned with the level");
+    }
+}
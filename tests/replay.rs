@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*};
+use jam7::{
+    game::{AppState, GameState},
+    headless::headless_app,
+    player::{Player, PlayerPlugin},
+    replay::{Replay, ReplayError, ReplayFrame, ReplayPlayback, ReplayPlugin},
+    target::Target,
+};
+
+/// One fixed tick per update, at the default fixed rate of 64 Hz
+const STEP: Duration = Duration::from_micros(15_625);
+const MAX_UPDATES: usize = 5_000;
+
+/// Walk forward while turning one way then the other, jumping now and then
+fn scripted_replay() -> Replay {
+    Replay {
+        seed: 0x00C0_FFEE,
+        frames: (0..600u16)
+            .map(|tick| ReplayFrame {
+                movement: Vec2::Y,
+                rotate: Vec2::new(if tick % 200 < 100 { 2.0 } else { -2.0 }, 0.0),
+                jump: tick % 90 == 0,
+                toggle_menu: false,
+            })
+            .collect(),
+    }
+}
+
+/// State of the game on the fixed tick the last frame was played
+#[derive(Resource, Debug, PartialEq)]
+struct Snapshot {
+    score: u32,
+    aquired_targets: u8,
+    player: Vec3,
+    targets: Vec<Vec3>,
+}
+
+fn play_back(replay: Replay, step: Duration) -> Snapshot {
+    let mut app = headless_app(step);
+    app.add_plugins((InputPlugin, PlayerPlugin, ReplayPlugin))
+        .add_systems(FixedPostUpdate, take_snapshot)
+        .insert_resource(ReplayPlayback::new(replay));
+    app.update();
+
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Loading);
+
+    let mut updates = 0;
+    while !app.world().contains_resource::<Snapshot>() {
+        assert!(updates < MAX_UPDATES, "Gave up after {MAX_UPDATES} updates");
+        app.update();
+        updates += 1;
+    }
+
+    app.world_mut()
+        .remove_resource::<Snapshot>()
+        .expect("The snapshot was taken")
+}
+
+fn take_snapshot(
+    mut commands: Commands,
+    playback: Option<Res<ReplayPlayback>>,
+    snapshot: Option<Res<Snapshot>>,
+    player: Single<&Transform, With<Player>>,
+    targets: Query<&Transform, With<Target>>,
+    game_state: Res<GameState>,
+) {
+    if snapshot.is_some() || !playback.is_some_and(|playback| playback.is_finished()) {
+        return;
+    }
+
+    let mut targets = targets
+        .iter()
+        .map(|transform| transform.translation)
+        .collect::<Vec<_>>();
+    targets.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+    commands.insert_resource(Snapshot {
+        score: game_state.score,
+        aquired_targets: game_state.aquired_targets,
+        player: player.translation,
+        targets,
+    });
+}
+
+#[test]
+fn replay_ends_in_the_same_state() {
+    let replay = Replay::from_bytes(&scripted_replay().to_bytes()).unwrap();
+    assert_eq!(replay, scripted_replay());
+
+    let first = play_back(replay.clone(), STEP);
+    let second = play_back(replay.clone(), STEP);
+    assert_eq!(first, second);
+
+    // The inputs were played back, not ignored
+    let idle = play_back(
+        Replay {
+            frames: vec![ReplayFrame::default(); replay.frames.len()],
+            ..replay
+        },
+        STEP,
+    );
+    assert_ne!(first.player, idle.player);
+}
+
+#[test]
+fn replay_does_not_depend_on_the_frame_rate() {
+    let reference = play_back(scripted_replay(), STEP);
+
+    // Two fixed ticks per update, then one every other update
+    for step in [STEP * 2, STEP / 2] {
+        let other = play_back(scripted_replay(), step);
+
+        assert_eq!(reference.score, other.score, "step {step:?}");
+        assert_eq!(
+            reference.aquired_targets, other.aquired_targets,
+            "step {step:?}"
+        );
+        // The targets steer every update, and the camera the player moves along follows
+        // it every update too, only the player lands close enough to compare
+        assert!(
+            reference.player.distance(other.player) < 0.05,
+            "step {step:?}: {} != {}",
+            reference.player,
+            other.player
+        );
+    }
+}
+
+#[test]
+fn replay_with_a_huge_frame_count_is_rejected() {
+    let mut bytes = Replay::default().to_bytes();
+    bytes[10..14].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    assert!(matches!(
+        Replay::from_bytes(&bytes),
+        Err(ReplayError::Truncated | ReplayError::TooLong(_))
+    ));
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use bevy::{input::InputPlugin, prelude::*};
+use bevy_prng::ChaCha20Rng;
+use bevy_rand::global::GlobalRng;
+use jam7::{
+    game::{AppState, GameState, PlayingState},
+    headless::headless_app,
+    physics::PlayerHitTarget,
+    player::PlayerPlugin,
+    seed::{PowerupRng, RunSeed},
+    target::Target,
+};
+use rand::Rng;
+
+const STEP: Duration = Duration::from_millis(100);
+const MAX_UPDATES: usize = 5_000;
+const ROUND_UPDATES: usize = 200;
+
+#[derive(Debug, PartialEq)]
+struct NextRound {
+    /// Next draw of the global rng, the level order is drawn from it
+    global: u32,
+    powerups: u32,
+    targets: Vec<Vec3>,
+}
+
+fn update_until(app: &mut App, mut done: impl FnMut(&App) -> bool) {
+    for _ in 0..MAX_UPDATES {
+        app.update();
+
+        if done(app) {
+            return;
+        }
+    }
+
+    panic!("Gave up after {MAX_UPDATES} updates");
+}
+
+fn playing(app: &App, difficulty: u8) -> bool {
+    app.world()
+        .get_resource::<State<PlayingState>>()
+        .is_some_and(|state| *state == PlayingState::Playing)
+        && app.world().resource::<GameState>().difficulty == difficulty
+}
+
+fn next_rng_draw<F: bevy::ecs::query::QueryFilter>(app: &mut App) -> u32 {
+    let world = app.world_mut();
+    world
+        .query_filtered::<&ChaCha20Rng, F>()
+        .single(world)
+        .expect("The rng is spawned once")
+        .clone()
+        .next_u32()
+}
+
+/// Play the first round with powerups spawning every `powerup_interval`, win it and look at the next one
+fn next_round(powerup_interval: f32) -> NextRound {
+    let mut app = headless_app(STEP);
+    app.add_plugins((InputPlugin, PlayerPlugin));
+    app.update();
+    app.insert_resource(RunSeed(0x00C0_FFEE));
+
+    app.world_mut()
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Loading);
+    update_until(&mut app, |app| playing(app, 1));
+
+    app.world_mut()
+        .resource_mut::<GameState>()
+        .powerup_timer
+        .set_duration(Duration::from_secs_f32(powerup_interval));
+    for _ in 0..ROUND_UPDATES {
+        app.update();
+    }
+
+    let world = app.world_mut();
+    let targets = world
+        .query_filtered::<Entity, With<Target>>()
+        .iter(world)
+        .collect::<Vec<_>>();
+    for target in targets {
+        world.trigger(PlayerHitTarget(target));
+    }
+    update_until(&mut app, |app| {
+        app.world()
+            .get_resource::<State<AppState>>()
+            .is_some_and(|state| *state == AppState::Playing)
+            && app.world().resource::<GameState>().difficulty == 2
+    });
+
+    let world = app.world_mut();
+    let mut targets = world
+        .query_filtered::<&Transform, With<Target>>()
+        .iter(world)
+        .map(|transform| transform.translation)
+        .collect::<Vec<_>>();
+    targets.sort_by(|a, b| a.x.total_cmp(&b.x));
+
+    NextRound {
+        global: next_rng_draw::<With<GlobalRng>>(&mut app),
+        powerups: next_rng_draw::<With<PowerupRng>>(&mut app),
+        targets,
+    }
+}
+
+#[test]
+fn powerup_timing_does_not_change_the_next_round() {
+    let rare = next_round(1_000.0);
+    let often = next_round(0.5);
+
+    // Powerups were drawn in one run and not in the other
+    assert_ne!(rare.powerups, often.powerups);
+
+    assert_eq!(rare.global, often.global);
+    assert!(!rare.targets.is_empty());
+    assert_eq!(rare.targets, often.targets);
+}